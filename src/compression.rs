@@ -0,0 +1,39 @@
+use crate::TradeMessage;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{self, Read, Write};
+
+impl TradeMessage {
+    /// Serialize the message to JSON and gzip it, for large `Batch` messages on the wire.
+    pub fn to_gzip(&self) -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&serde_json::to_vec(self)?)?;
+        encoder.finish()
+    }
+
+    /// Inverse of [`TradeMessage::to_gzip`]. Invalid JSON is reported as an `InvalidData` error.
+    pub fn from_gzip(bytes: &[u8]) -> io::Result<TradeMessage> {
+        let mut json = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut json)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Side, TradeIntent};
+
+    #[test]
+    fn can_round_trip_gzipped_batch() {
+        let intents = (0..1000)
+            .map(|i| TradeIntent::new("AAPL", Side::Buy, i).limit(150))
+            .collect();
+        let message = TradeMessage::Batch { intents };
+        let compressed = message.to_gzip().unwrap();
+        assert!(compressed.len() < serde_json::to_vec(&message).unwrap().len() / 2);
+        assert_eq!(TradeMessage::from_gzip(&compressed).unwrap(), message);
+        assert!(TradeMessage::from_gzip(b"not gzip").is_err());
+    }
+}
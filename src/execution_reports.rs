@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+}
+
+/// The state of a [`TradeIntent`](crate::TradeIntent) as reported by the execution layer.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ExecutionReport {
+    /// The `id` of the `TradeIntent` this report refers to.
+    pub intent_id: Uuid,
+    pub ticker: String,
+    pub status: OrderStatus,
+    /// Signed quantity filled so far, positive for buys and negative for sells.
+    pub filled_qty: isize,
+    /// Average price of the fills so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_price: Option<Decimal>,
+    pub timestamp: DateTime<Utc>,
+}
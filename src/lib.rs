@@ -1,12 +1,28 @@
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use thiserror::Error;
 
+#[cfg(feature = "gzip")]
+mod compression;
+mod execution_reports;
+pub use execution_reports::{ExecutionReport, OrderStatus};
+mod limits;
+pub use limits::{max_string_length, set_max_string_length, DEFAULT_MAX_STRING_LENGTH};
 mod position_intents;
 pub use position_intents::{
-    Amount, Identifier, PositionIntent, PositionIntentBuilder, UpdatePolicy,
+    aggregate, group_by_strategy, group_by_strategy_key, latest_per_identifier,
+    validate_strategy_cap, Amount, AmountUnit, AssetClass, FieldChange, FlatAmount, Identifier,
+    IntentLifetime, PositionIntent, PositionIntentBuilder, Priority, RiskView, UpdatePolicy,
+    COMPACT_FIELDS, PATCHABLE_FIELDS,
 };
 mod trade_intents;
-pub use trade_intents::{OrderType, TimeInForce, TradeIntent, TradeMessage};
+pub use trade_intents::{
+    read_messages, NestedTradeIntent, OcoGroup, OrderType, SequenceTracker, Side,
+    SimplifiedOrderType, TimeInForce, TradeEnvelope, TradeIntent, TradeMessage,
+    UppercaseTimeInForce, MAX_CLIENT_ORDER_ID_LENGTH,
+};
+mod version;
+pub use version::{is_supported_version, CURRENT_VERSION, MIN_SUPPORTED_VERSION};
 
 #[derive(Error, Clone, Debug)]
 pub enum Error {
@@ -14,8 +30,216 @@ pub enum Error {
         "Non-`Zero` `AmountSpec`s of different type cannot be merged.\nLeft: {0:?}, Right: {1:?}"
     )]
     IncompatibleAmountError(Amount, Amount),
+    #[error("Could not interpret value as an `Amount`: {0}")]
+    InvalidAmount(String),
+    #[error("Amount `{0:?}` overflows when combined with {1}")]
+    AmountOverflow(Amount, Decimal),
+    #[error("Lower bound `{0:?}` is above upper bound `{1:?}`")]
+    InvalidBounds(Amount, Amount),
+    #[error("Only whole shares are allowed, got {0}")]
+    FractionalShares(Decimal),
+    #[error("Cannot size a position from a stop distance of {0}")]
+    InvalidStopDistance(Decimal),
+    #[error("A decision price is required but was not set")]
+    MissingDecisionPrice,
+    #[error("Prices must be positive, got {0}")]
+    InvalidPrice(Decimal),
+    #[error("Price {0} is not a multiple of the tick size {1}")]
+    InvalidTick(Decimal, Decimal),
+    #[error("Limit price {0} is more than {2} bps away from the decision price {1}")]
+    LimitOutsideBand(Decimal, Decimal, u32),
+    #[error("Could not decode compact `PositionIntent` array: {0}")]
+    InvalidCompactArray(String),
+    #[error("Could not apply patch to `PositionIntent`: {0}")]
+    InvalidPatch(String),
+    #[error("Strategy names must be non-empty and must not contain `:`, got `{0}`")]
+    InvalidStrategyName(String),
     #[error("Cannot create PositionIntent with `before` < `after`. \nBefore: {0}, After: {1}")]
     InvalidBeforeAfter(DateTime<Utc>, DateTime<Utc>),
     #[error("Identifier `All` can only be used with the `Dollars` and `Shares` `Amount`s")]
     InvalidCombination,
+    #[error(
+        "Identifier `All` can only be used with the `Update` and `Retain` policies, got `{0:?}`"
+    )]
+    InvalidAllPolicy(UpdatePolicy),
+    #[error("Update policy `{0:?}` contradicts the direction of amount `{1:?}`")]
+    InvalidPolicyAmount(UpdatePolicy, Amount),
+    #[error(
+        "Bracket must surround the decision price.\nTake profit: {0}, Stop loss: {1}, Decision price: {2}"
+    )]
+    InvalidBracket(Decimal, Decimal, Decimal),
+    #[error("Strategy `{0}` targets {1} dollars, exceeding its cap of {2}")]
+    StrategyCapExceeded(String, Decimal, Decimal),
+    #[error("Extended-hours orders must be `Limit` orders. Order type: {0:?}")]
+    InvalidExtendedHours(OrderType),
+    #[error("Order type `{0:?}` cannot be used with time in force `{1:?}`")]
+    InvalidTimeInForce(OrderType, TimeInForce),
+    #[error("A good-til date can only be used with time in force `GoodTilCanceled`, got `{0:?}`")]
+    InvalidGoodTilDate(TimeInForce),
+    #[error("Client order id exceeds the maximum length of {1} characters: {0}")]
+    InvalidClientOrderId(String, usize),
+    #[error("Invalid OCO group: {0}")]
+    InvalidOcoGroup(String),
+    #[error("A `Batch` message must contain at least one intent")]
+    EmptyBatch,
+    #[error("Failed to parse order type: {0}")]
+    ParseOrderType(String),
+}
+
+impl Error {
+    /// Stable, machine-readable code identifying the kind of error, for clients that shouldn't
+    /// depend on the `Display` text.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::IncompatibleAmountError(_, _) => "incompatible_amount",
+            Error::InvalidAmount(_) => "invalid_amount",
+            Error::AmountOverflow(_, _) => "amount_overflow",
+            Error::InvalidBounds(_, _) => "invalid_bounds",
+            Error::FractionalShares(_) => "fractional_shares",
+            Error::InvalidStopDistance(_) => "invalid_stop_distance",
+            Error::MissingDecisionPrice => "missing_decision_price",
+            Error::InvalidPrice(_) => "invalid_price",
+            Error::InvalidTick(_, _) => "invalid_tick",
+            Error::LimitOutsideBand(_, _, _) => "limit_outside_band",
+            Error::InvalidCompactArray(_) => "invalid_compact_array",
+            Error::InvalidPatch(_) => "invalid_patch",
+            Error::InvalidStrategyName(_) => "invalid_strategy_name",
+            Error::InvalidBeforeAfter(_, _) => "invalid_window",
+            Error::InvalidCombination => "invalid_combination",
+            Error::InvalidAllPolicy(_) => "invalid_all_policy",
+            Error::InvalidPolicyAmount(_, _) => "invalid_policy_amount",
+            Error::InvalidBracket(_, _, _) => "invalid_bracket",
+            Error::StrategyCapExceeded(_, _, _) => "strategy_cap_exceeded",
+            Error::InvalidExtendedHours(_) => "invalid_extended_hours",
+            Error::InvalidTimeInForce(_, _) => "invalid_time_in_force",
+            Error::InvalidGoodTilDate(_) => "invalid_good_til_date",
+            Error::InvalidClientOrderId(_, _) => "invalid_client_order_id",
+            Error::InvalidOcoGroup(_) => "invalid_oco_group",
+            Error::EmptyBatch => "empty_batch",
+            Error::ParseOrderType(_) => "parse_order_type",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Position of the variant in `Error`. Has no wildcard arm, so adding a variant fails to
+    /// compile until it's listed here, and then fails `every_error_has_a_code` until it's covered.
+    fn variant_index(error: &Error) -> usize {
+        match error {
+            Error::IncompatibleAmountError(_, _) => 0,
+            Error::InvalidAmount(_) => 1,
+            Error::AmountOverflow(_, _) => 2,
+            Error::InvalidBounds(_, _) => 3,
+            Error::FractionalShares(_) => 4,
+            Error::InvalidStopDistance(_) => 5,
+            Error::MissingDecisionPrice => 6,
+            Error::InvalidPrice(_) => 7,
+            Error::InvalidTick(_, _) => 8,
+            Error::LimitOutsideBand(_, _, _) => 9,
+            Error::InvalidCompactArray(_) => 10,
+            Error::InvalidPatch(_) => 11,
+            Error::InvalidStrategyName(_) => 12,
+            Error::InvalidBeforeAfter(_, _) => 13,
+            Error::InvalidCombination => 14,
+            Error::InvalidAllPolicy(_) => 15,
+            Error::InvalidPolicyAmount(_, _) => 16,
+            Error::InvalidBracket(_, _, _) => 17,
+            Error::StrategyCapExceeded(_, _, _) => 18,
+            Error::InvalidExtendedHours(_) => 19,
+            Error::InvalidTimeInForce(_, _) => 20,
+            Error::InvalidGoodTilDate(_) => 21,
+            Error::InvalidClientOrderId(_, _) => 22,
+            Error::InvalidOcoGroup(_) => 23,
+            Error::EmptyBatch => 24,
+            Error::ParseOrderType(_) => 25,
+        }
+    }
+
+    const VARIANTS: usize = 26;
+
+    #[test]
+    fn every_error_has_a_code() {
+        let amount = Amount::Zero;
+        let price = Decimal::ONE;
+        let now = Utc::now();
+        let cases = [
+            (
+                Error::IncompatibleAmountError(amount.clone(), amount.clone()),
+                "incompatible_amount",
+            ),
+            (Error::InvalidAmount("x".into()), "invalid_amount"),
+            (
+                Error::AmountOverflow(amount.clone(), price),
+                "amount_overflow",
+            ),
+            (
+                Error::InvalidBounds(amount.clone(), amount.clone()),
+                "invalid_bounds",
+            ),
+            (Error::FractionalShares(price), "fractional_shares"),
+            (Error::InvalidStopDistance(price), "invalid_stop_distance"),
+            (Error::MissingDecisionPrice, "missing_decision_price"),
+            (Error::InvalidPrice(price), "invalid_price"),
+            (Error::InvalidTick(price, price), "invalid_tick"),
+            (
+                Error::LimitOutsideBand(price, price, 1),
+                "limit_outside_band",
+            ),
+            (
+                Error::InvalidCompactArray("x".into()),
+                "invalid_compact_array",
+            ),
+            (Error::InvalidPatch("x".into()), "invalid_patch"),
+            (
+                Error::InvalidStrategyName("".into()),
+                "invalid_strategy_name",
+            ),
+            (Error::InvalidBeforeAfter(now, now), "invalid_window"),
+            (Error::InvalidCombination, "invalid_combination"),
+            (
+                Error::InvalidAllPolicy(UpdatePolicy::RetainLong),
+                "invalid_all_policy",
+            ),
+            (
+                Error::InvalidPolicyAmount(UpdatePolicy::Update, amount),
+                "invalid_policy_amount",
+            ),
+            (
+                Error::InvalidBracket(price, price, price),
+                "invalid_bracket",
+            ),
+            (
+                Error::StrategyCapExceeded("x".into(), price, price),
+                "strategy_cap_exceeded",
+            ),
+            (
+                Error::InvalidExtendedHours(OrderType::Market),
+                "invalid_extended_hours",
+            ),
+            (
+                Error::InvalidTimeInForce(OrderType::Market, TimeInForce::Day),
+                "invalid_time_in_force",
+            ),
+            (
+                Error::InvalidGoodTilDate(TimeInForce::Day),
+                "invalid_good_til_date",
+            ),
+            (
+                Error::InvalidClientOrderId("x".into(), MAX_CLIENT_ORDER_ID_LENGTH),
+                "invalid_client_order_id",
+            ),
+            (Error::InvalidOcoGroup("x".into()), "invalid_oco_group"),
+            (Error::EmptyBatch, "empty_batch"),
+            (Error::ParseOrderType("x".into()), "parse_order_type"),
+        ];
+        let mut covered = std::collections::BTreeSet::new();
+        for (error, code) in cases {
+            assert_eq!(error.code(), code);
+            covered.insert(variant_index(&error));
+        }
+        assert_eq!(covered, (0..VARIANTS).collect());
+    }
 }
@@ -0,0 +1,108 @@
+use serde::{de, Deserialize, Deserializer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default maximum length, in bytes, of identifying strings such as strategy names and tickers.
+pub const DEFAULT_MAX_STRING_LENGTH: usize = 64;
+
+static MAX_STRING_LENGTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_STRING_LENGTH);
+
+/// Held by tests that depend on the string length limit, so changing it in one test can't
+/// break another running concurrently.
+#[cfg(test)]
+pub(crate) static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Set the maximum length of strategy, sub-strategy and ticker strings accepted during
+/// deserialization. This applies process-wide, to every thread.
+pub fn set_max_string_length(length: usize) {
+    MAX_STRING_LENGTH.store(length, Ordering::Relaxed)
+}
+
+/// The maximum length of strategy, sub-strategy and ticker strings currently accepted during
+/// deserialization. This is a global setting shared by every thread, defaulting to
+/// [`DEFAULT_MAX_STRING_LENGTH`].
+pub fn max_string_length() -> usize {
+    MAX_STRING_LENGTH.load(Ordering::Relaxed)
+}
+
+pub(crate) fn check_length<E: de::Error>(s: String) -> Result<String, E> {
+    let max = max_string_length();
+    if s.len() > max {
+        return Err(E::custom(format!(
+            "string of length {} exceeds maximum length of {}",
+            s.len(),
+            max
+        )));
+    }
+    Ok(s)
+}
+
+pub(crate) fn bounded_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    check_length(String::deserialize(deserializer)?)
+}
+
+pub(crate) fn bounded_option_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(check_length)
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PositionIntent, TradeIntent};
+
+    fn intent_json(strategy: &str, sub_strategy: &str, ticker: &str) -> String {
+        format!(
+            r#"{{"id":"7b2c5f2c-5c1e-4f5b-8d3a-6a8f1b7e9c10","strategy":"{}","sub_strategy":"{}","timestamp":"2021-10-27T14:30:00Z","identifier":{{"ticker":"{}"}},"amount":"zero","update_policy":"update"}}"#,
+            strategy, sub_strategy, ticker
+        )
+    }
+
+    #[test]
+    fn accepts_strings_at_limit() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        let at_limit = "A".repeat(DEFAULT_MAX_STRING_LENGTH);
+        let data = intent_json(&at_limit, &at_limit, &at_limit);
+        assert!(serde_json::from_str::<PositionIntent>(&data).is_ok());
+    }
+
+    #[test]
+    fn rejects_strings_over_limit() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        let over_limit = "A".repeat(DEFAULT_MAX_STRING_LENGTH + 1);
+        let data = intent_json(&over_limit, "B", "AAPL");
+        assert!(serde_json::from_str::<PositionIntent>(&data).is_err());
+        let data = intent_json("A", &over_limit, "AAPL");
+        assert!(serde_json::from_str::<PositionIntent>(&data).is_err());
+        let data = intent_json("A", "B", &over_limit);
+        assert!(serde_json::from_str::<PositionIntent>(&data).is_err());
+
+        let data = format!(
+            r#"{{"id":"7b2c5f2c-5c1e-4f5b-8d3a-6a8f1b7e9c10","ticker":"{}","qty":10,"order_type":"market","time_in_force":"day"}}"#,
+            over_limit
+        );
+        assert!(serde_json::from_str::<TradeIntent>(&data).is_err());
+    }
+
+    #[test]
+    fn can_change_max_string_length() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        let raised = 2 * DEFAULT_MAX_STRING_LENGTH;
+        set_max_string_length(raised);
+        assert_eq!(max_string_length(), raised);
+        let at_limit = "A".repeat(raised);
+        let accepted = serde_json::from_str::<PositionIntent>(&intent_json("A", "B", &at_limit));
+        let over_limit = "A".repeat(raised + 1);
+        let rejected = serde_json::from_str::<PositionIntent>(&intent_json("A", "B", &over_limit));
+        set_max_string_length(DEFAULT_MAX_STRING_LENGTH);
+        assert!(accepted.is_ok());
+        assert!(rejected.is_err());
+        assert_eq!(max_string_length(), DEFAULT_MAX_STRING_LENGTH);
+    }
+}
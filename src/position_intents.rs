@@ -1,23 +1,82 @@
-use crate::Error;
-use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use crate::{Error, OrderType, Side};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::{Signed, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{de, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum UpdatePolicy {
+    #[serde(alias = "hold")]
     Retain,
     RetainLong,
     RetainShort,
+    #[serde(alias = "replace")]
+    #[default]
     Update,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+impl UpdatePolicy {
+    /// Combine the policies of two merged intents. Each policy is viewed as the set of existing
+    /// positions it retains (`Retain`: long and short, `RetainLong`: long, `RetainShort`: short,
+    /// `Update`: none), and the result retains only what both retain. So `Update` dominates
+    /// everything, `Retain` yields to everything, and `RetainLong` with `RetainShort` gives
+    /// `Update`. The operation is commutative and idempotent.
+    pub fn resolve(self, other: UpdatePolicy) -> UpdatePolicy {
+        use UpdatePolicy::*;
+        match (self, other) {
+            (Update, _) | (_, Update) => Update,
+            (Retain, x) | (x, Retain) => x,
+            (RetainLong, RetainLong) => RetainLong,
+            (RetainShort, RetainShort) => RetainShort,
+            (RetainLong, RetainShort) | (RetainShort, RetainLong) => Update,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            UpdatePolicy::Retain => "retain",
+            UpdatePolicy::RetainLong => "retain_long",
+            UpdatePolicy::RetainShort => "retain_short",
+            UpdatePolicy::Update => "update",
+        }
+    }
+}
+
+/// Whether an intent should be cleaned up at the end of the trading session or persist until it
+/// is superseded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentLifetime {
+    Day,
+    #[default]
+    Persistent,
+}
+
+/// How urgently the order-manager should process an intent when it is saturated.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Values are serialized as strings, but both strings and JSON numbers are accepted when
+/// deserializing.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum Amount {
     Dollars(Decimal),
     Shares(Decimal),
+    #[default]
     Zero,
 }
 impl Amount {
@@ -55,21 +114,435 @@ impl Amount {
             Amount::Zero => false,
         }
     }
+
+    /// Collapse `Dollars(0)` and `Shares(0)` into `Amount::Zero`. Useful before
+    /// [`Amount::merge`], where a zero of one variant would otherwise lock the result into that
+    /// variant and make merging with the other variant fail.
+    pub fn normalize_zero(self) -> Amount {
+        if self.is_zero() {
+            Amount::Zero
+        } else {
+            self
+        }
+    }
+
+    /// Whether the amount targets a long position, i.e. is positive and nonzero.
+    pub fn is_long(&self) -> bool {
+        self.signum() > Decimal::ZERO
+    }
+
+    /// Whether the amount targets a short position, i.e. is negative and nonzero.
+    pub fn is_short(&self) -> bool {
+        self.signum() < Decimal::ZERO
+    }
+
+    /// `1`, `-1` or `0` depending on the sign of the inner value.
+    pub fn signum(&self) -> Decimal {
+        match self {
+            Amount::Dollars(x) => x.signum(),
+            Amount::Shares(x) => x.signum(),
+            Amount::Zero => Decimal::ZERO,
+        }
+    }
+
+    /// Error if this is a `Shares` amount with a fractional part, for venues that only trade
+    /// whole shares.
+    pub fn require_whole_shares(&self) -> Result<(), Error> {
+        match self {
+            Amount::Shares(x) if !x.fract().is_zero() => Err(Error::FractionalShares(*x)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Clamp the inner value to the band `[min, max]`. All non-`Zero` amounts must be of the same
+    /// variant; `Zero` is treated as `0` of that variant. Bounds with `min > max` are rejected.
+    pub fn clamp(self, min: Amount, max: Amount) -> Result<Amount, Error> {
+        let unit = self.clone().merge(min.clone())?.merge(max.clone())?;
+        if min.value() > max.value() {
+            return Err(Error::InvalidBounds(min, max));
+        }
+        let value = self.value().max(min.value()).min(max.value());
+        Ok(match unit {
+            Amount::Dollars(_) => Amount::Dollars(value),
+            Amount::Shares(_) => Amount::Shares(value),
+            Amount::Zero => Amount::Zero,
+        })
+    }
+
+    /// Multiply the inner value by `factor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result overflows a `Decimal`. Use [`Amount::checked_scale_by`] for factors
+    /// that aren't known to be small.
+    pub fn scale_by(&self, factor: Decimal) -> Amount {
+        match self {
+            Amount::Dollars(x) => Amount::Dollars(x * factor),
+            Amount::Shares(x) => Amount::Shares(x * factor),
+            Amount::Zero => Amount::Zero,
+        }
+    }
+
+    /// Multiply the inner value by `factor`, returning an error on overflow.
+    pub fn checked_scale_by(&self, factor: Decimal) -> Result<Amount, Error> {
+        let overflow = || Error::AmountOverflow(self.clone(), factor);
+        match self {
+            Amount::Dollars(x) => Ok(Amount::Dollars(x.checked_mul(factor).ok_or_else(overflow)?)),
+            Amount::Shares(x) => Ok(Amount::Shares(x.checked_mul(factor).ok_or_else(overflow)?)),
+            Amount::Zero => Ok(Amount::Zero),
+        }
+    }
+
+    /// Divide the amount into `n` parts that sum exactly to it. Parts are rounded towards zero at
+    /// the scale of the inner value, with the remainder added to the last part, so e.g. 10 shares
+    /// split three ways gives 3, 3 and 4. Returns no parts if `n` is zero.
+    pub fn split(&self, n: usize) -> Vec<Amount> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let split = |x: &Decimal| {
+            let part =
+                (x / Decimal::from(n)).round_dp_with_strategy(x.scale(), RoundingStrategy::ToZero);
+            let last = x - part * Decimal::from(n - 1);
+            let mut parts = vec![part; n - 1];
+            parts.push(last);
+            parts
+        };
+        match self {
+            Amount::Dollars(x) => split(x).into_iter().map(Amount::Dollars).collect(),
+            Amount::Shares(x) => split(x).into_iter().map(Amount::Shares).collect(),
+            Amount::Zero => vec![Amount::Zero; n],
+        }
+    }
+
+    /// Express the amount in shares at `price`, rounded to `dp` decimal places with
+    /// `RoundingStrategy::MidpointAwayFromZero`. The sign of the amount is preserved, and
+    /// non-positive prices are rejected.
+    pub fn convert_to_shares(&self, price: Decimal, dp: u32) -> Result<Amount, Error> {
+        self.convert_to_shares_with_rounding(price, dp, RoundingStrategy::MidpointAwayFromZero)
+    }
+
+    pub fn convert_to_shares_with_rounding(
+        &self,
+        price: Decimal,
+        dp: u32,
+        rounding: RoundingStrategy,
+    ) -> Result<Amount, Error> {
+        if price <= Decimal::ZERO {
+            return Err(Error::InvalidPrice(price));
+        }
+        match self {
+            Amount::Dollars(x) => {
+                let shares = x.checked_div(price).ok_or(Error::InvalidPrice(price))?;
+                Ok(Amount::Shares(shares.round_dp_with_strategy(dp, rounding)))
+            }
+            Amount::Shares(x) => Ok(Amount::Shares(x.round_dp_with_strategy(dp, rounding))),
+            Amount::Zero => Ok(Amount::Zero),
+        }
+    }
+
+    /// Express the amount in dollars at `price`, rounded to `dp` decimal places with
+    /// `RoundingStrategy::MidpointAwayFromZero`. The sign of the amount is preserved, and
+    /// non-positive prices are rejected.
+    pub fn convert_to_dollars(&self, price: Decimal, dp: u32) -> Result<Amount, Error> {
+        self.convert_to_dollars_with_rounding(price, dp, RoundingStrategy::MidpointAwayFromZero)
+    }
+
+    pub fn convert_to_dollars_with_rounding(
+        &self,
+        price: Decimal,
+        dp: u32,
+        rounding: RoundingStrategy,
+    ) -> Result<Amount, Error> {
+        if price <= Decimal::ZERO {
+            return Err(Error::InvalidPrice(price));
+        }
+        match self {
+            Amount::Dollars(x) => Ok(Amount::Dollars(x.round_dp_with_strategy(dp, rounding))),
+            Amount::Shares(x) => {
+                let dollars = x
+                    .checked_mul(price)
+                    .ok_or_else(|| Error::AmountOverflow(self.clone(), price))?;
+                Ok(Amount::Dollars(
+                    dollars.round_dp_with_strategy(dp, rounding),
+                ))
+            }
+            Amount::Zero => Ok(Amount::Zero),
+        }
+    }
+
+    /// Extract an `Amount` from dynamic JSON, accepting both the default externally-tagged form
+    /// (e.g. `{"shares": "100"}` or `"zero"`) and the [`FlatAmount`] `{"type", "value"}` form.
+    pub fn from_value(value: &serde_json::Value) -> Result<Amount, Error> {
+        Amount::deserialize(value)
+            .or_else(|_| FlatAmount::deserialize(value).map(Amount::from))
+            .map_err(|_| Error::InvalidAmount(value.to_string()))
+    }
+
+    /// Format the inner value with exactly `decimals` decimal places (e.g. `2` for dollars, `0`
+    /// for whole shares), rounding midpoints away from zero. `Zero` renders as `0`.
+    pub fn to_broker_string(&self, decimals: u32) -> String {
+        match self {
+            Amount::Dollars(x) | Amount::Shares(x) => {
+                let mut rounded =
+                    x.round_dp_with_strategy(decimals, RoundingStrategy::MidpointAwayFromZero);
+                rounded.rescale(decimals);
+                rounded.to_string()
+            }
+            Amount::Zero => "0".into(),
+        }
+    }
+
+    /// The inner value as an `f64`, for metrics. Values with more significant digits than an
+    /// `f64` can hold lose precision. `Zero` is `0.0`.
+    pub fn to_f64(&self) -> f64 {
+        self.value().to_f64().unwrap_or_default()
+    }
+
+    /// The share count truncated towards zero, saturating at the bounds of `i64`. `None` for
+    /// `Dollars`, and `Some(0)` for `Zero`.
+    pub fn to_i64_shares(&self) -> Option<i64> {
+        match self {
+            Amount::Shares(x) => Some(x.trunc().to_i64().unwrap_or(if x.is_sign_negative() {
+                i64::MIN
+            } else {
+                i64::MAX
+            })),
+            Amount::Zero => Some(0),
+            Amount::Dollars(_) => None,
+        }
+    }
+
+    /// The inner value multiplied by `10^scale`, e.g. integer cents for `scale = 2`. `None` if
+    /// the result doesn't fit in an `i128` or the value has more than `scale` decimal places.
+    pub fn to_scaled_i128(&self, scale: u32) -> Option<i128> {
+        let value = self.value();
+        let mantissa = value.mantissa();
+        if scale >= value.scale() {
+            10i128
+                .checked_pow(scale - value.scale())
+                .and_then(|factor| mantissa.checked_mul(factor))
+        } else {
+            let divisor = 10i128.pow(value.scale() - scale);
+            (mantissa % divisor == 0).then(|| mantissa / divisor)
+        }
+    }
+
+    /// Inverse of [`Amount::to_scaled_i128`], with `variant` choosing the kind of amount, e.g.
+    /// `Amount::from_scaled_i128(12345, 2, Amount::Dollars)` for `$123.45`. `None` if the value
+    /// can't be represented as a `Decimal`.
+    pub fn from_scaled_i128(
+        value: i128,
+        scale: u32,
+        variant: fn(Decimal) -> Amount,
+    ) -> Option<Amount> {
+        Decimal::try_from_i128_with_scale(value, scale)
+            .ok()
+            .map(variant)
+    }
+
+    pub fn unit(&self) -> AmountUnit {
+        match self {
+            Amount::Dollars(_) => AmountUnit::Dollars,
+            Amount::Shares(_) => AmountUnit::Shares,
+            Amount::Zero => AmountUnit::Zero,
+        }
+    }
+
+    /// Compare two amounts by value. Amounts of the same variant are ordered by value, and `Zero`
+    /// is ordered as `0` against either variant. `Dollars` and `Shares` are not comparable, so
+    /// this returns `None` for them. This is deliberately not a `PartialOrd` impl: it isn't
+    /// transitive across variants, and `Zero` compares `Equal` to `Dollars(0)` even though `==`
+    /// tells them apart.
+    pub fn compare(&self, other: &Amount) -> Option<Ordering> {
+        match (self, other) {
+            (Amount::Dollars(_), Amount::Shares(_)) | (Amount::Shares(_), Amount::Dollars(_)) => {
+                None
+            }
+            (x, y) => x.value().partial_cmp(&y.value()),
+        }
+    }
+
+    fn value(&self) -> Decimal {
+        match self {
+            Amount::Dollars(x) => *x,
+            Amount::Shares(x) => *x,
+            Amount::Zero => Decimal::ZERO,
+        }
+    }
 }
 
+/// The unit an [`Amount`] is denominated in, without its value.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AmountUnit {
+    Dollars,
+    Shares,
+    Zero,
+}
+
+/// Renders dollars as `$100.5` / `-$100.5`, shares as `100 sh` and `Zero` as `0`.
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Amount::Dollars(x) if x.is_sign_negative() => write!(f, "-${}", x.abs()),
+            Amount::Dollars(x) => write!(f, "${}", x),
+            Amount::Shares(x) => write!(f, "{} sh", x),
+            Amount::Zero => write!(f, "0"),
+        }
+    }
+}
+
+/// Alternate wire representation of [`Amount`] as a flat `{"type": ..., "value": ...}` object,
+/// for consumers that can't handle the externally-tagged default.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum FlatAmount {
+    Dollars(Decimal),
+    Shares(Decimal),
+    Zero,
+}
+
+impl From<Amount> for FlatAmount {
+    fn from(amount: Amount) -> Self {
+        match amount {
+            Amount::Dollars(x) => FlatAmount::Dollars(x),
+            Amount::Shares(x) => FlatAmount::Shares(x),
+            Amount::Zero => FlatAmount::Zero,
+        }
+    }
+}
+
+impl From<FlatAmount> for Amount {
+    fn from(amount: FlatAmount) -> Self {
+        match amount {
+            FlatAmount::Dollars(x) => Amount::Dollars(x),
+            FlatAmount::Shares(x) => Amount::Shares(x),
+            FlatAmount::Zero => Amount::Zero,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Equity,
+    Crypto,
+}
+
+impl AssetClass {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AssetClass::Equity => "equity",
+            AssetClass::Crypto => "crypto",
+        }
+    }
+}
+
+/// Besides the tagged form, deserializes a bare string as a `Ticker`, with `"*"` meaning `All`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum Identifier {
     Ticker(String),
+    /// A symbol qualified with its asset class, for symbols such as `BTC/USD` that need
+    /// different handling than plain equity tickers.
+    Asset {
+        symbol: String,
+        class: AssetClass,
+    },
+    All,
+}
+
+impl Identifier {
+    pub fn asset(symbol: impl Into<String>, class: AssetClass) -> Self {
+        Identifier::Asset {
+            symbol: symbol.into(),
+            class,
+        }
+    }
+
+    /// The ticker or asset symbol, or `None` for `All`.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Identifier::Ticker(ticker) => Some(ticker),
+            Identifier::Asset { symbol, .. } => Some(symbol),
+            Identifier::All => None,
+        }
+    }
+
+    /// The asset class of an `Asset`. Plain tickers don't carry one.
+    pub fn asset_class(&self) -> Option<AssetClass> {
+        match self {
+            Identifier::Asset { class, .. } => Some(*class),
+            Identifier::Ticker(_) | Identifier::All => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TaggedIdentifier {
+    Ticker(#[serde(deserialize_with = "crate::limits::bounded_string")] String),
+    Asset {
+        #[serde(deserialize_with = "crate::limits::bounded_string")]
+        symbol: String,
+        class: AssetClass,
+    },
     All,
 }
 
+impl From<TaggedIdentifier> for Identifier {
+    fn from(tagged: TaggedIdentifier) -> Self {
+        match tagged {
+            TaggedIdentifier::Ticker(ticker) => Identifier::Ticker(ticker),
+            TaggedIdentifier::Asset { symbol, class } => Identifier::Asset { symbol, class },
+            TaggedIdentifier::All => Identifier::All,
+        }
+    }
+}
+
+/// Dispatches on the shape of the input rather than using an untagged enum, so that errors from
+/// the tagged form, such as over-long tickers, are reported as is.
+struct IdentifierVisitor;
+
+impl<'de> de::Visitor<'de> for IdentifierVisitor {
+    type Value = Identifier;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a ticker string or a tagged identifier")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Identifier, E> {
+        match s {
+            "*" | "all" => Ok(Identifier::All),
+            ticker => Ok(Identifier::Ticker(crate::limits::check_length(
+                ticker.to_string(),
+            )?)),
+        }
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Identifier, A::Error> {
+        TaggedIdentifier::deserialize(de::value::MapAccessDeserializer::new(map))
+            .map(Identifier::from)
+    }
+}
+
+impl<'de> Deserialize<'de> for Identifier {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(IdentifierVisitor)
+    }
+}
+
 impl<T: ToString> From<T> for Identifier {
     fn from(s: T) -> Self {
         Self::Ticker(s.to_string())
     }
 }
 
+/// Builder for [`PositionIntent`]s. Amount and price setters accept anything convertible into a
+/// `Decimal`, so integer literals can be passed directly. Floats have no lossless conversion and
+/// must be converted explicitly, e.g. with `Decimal::try_from(1.5)`, which rounds to the nearest
+/// representable `Decimal` and so may not be exactly the value written.
 #[derive(Debug, Clone)]
 pub struct PositionIntentBuilder {
     strategy: String,
@@ -77,31 +550,125 @@ pub struct PositionIntentBuilder {
     identifier: Identifier,
     amount: Amount,
     update_policy: UpdatePolicy,
+    lifetime: IntentLifetime,
+    priority: Priority,
     decision_price: Option<Decimal>,
+    signal: Option<Decimal>,
     limit_price: Option<Decimal>,
     stop_price: Option<Decimal>,
+    take_profit: Option<Decimal>,
+    stop_loss: Option<Decimal>,
     before: Option<DateTime<Utc>>,
     after: Option<DateTime<Utc>>,
+    tags: BTreeMap<String, String>,
+    idempotency_key: Option<String>,
+    if_not_exists: bool,
+    infer_decision_price: bool,
+    leverage: Option<Decimal>,
+    limit_band_bps: Option<u32>,
+    limit_offset_bps: Option<i32>,
 }
 
 impl PositionIntentBuilder {
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    pub fn dollars(mut self, dollars: impl Into<Decimal>) -> Self {
+        self.amount = Amount::Dollars(dollars.into());
+        self
+    }
+
+    pub fn shares(mut self, shares: impl Into<Decimal>) -> Self {
+        self.amount = Amount::Shares(shares.into());
+        self
+    }
+
+    /// Size the position in shares so that a move of `stop_distance` against it loses
+    /// `risk_dollars`. The stop distance must be positive.
+    pub fn size_from_risk(
+        mut self,
+        risk_dollars: impl Into<Decimal>,
+        stop_distance: impl Into<Decimal>,
+    ) -> Result<Self, Error> {
+        let risk_dollars = risk_dollars.into();
+        let stop_distance = stop_distance.into();
+        if stop_distance <= Decimal::ZERO {
+            return Err(Error::InvalidStopDistance(stop_distance));
+        }
+        let shares = risk_dollars
+            .checked_div(stop_distance)
+            .ok_or(Error::AmountOverflow(
+                Amount::Dollars(risk_dollars),
+                stop_distance,
+            ))?;
+        self.amount = Amount::Shares(shares);
+        Ok(self)
+    }
+
+    /// Multiply a `Dollars` amount by `mult` when building. `Shares` and `Zero` amounts are left
+    /// unchanged, since leverage only changes the capital behind a position, not a share count.
+    pub fn leverage(mut self, mult: Decimal) -> Self {
+        self.leverage = Some(mult);
+        self
+    }
+
     pub fn sub_strategy(mut self, sub_strategy: impl Into<String>) -> Self {
         self.sub_strategy = Some(sub_strategy.into());
         self
     }
 
-    pub fn decision_price(mut self, decision_price: Decimal) -> Self {
-        self.decision_price = Some(decision_price);
+    pub fn decision_price(mut self, decision_price: impl Into<Decimal>) -> Self {
+        self.decision_price = Some(decision_price.into());
+        self
+    }
+
+    pub fn signal(mut self, signal: impl Into<Decimal>) -> Self {
+        self.signal = Some(signal.into());
+        self
+    }
+
+    /// When enabled, `build` uses the `limit_price` as the `decision_price` if no decision price
+    /// was set explicitly. An explicit `decision_price` always takes precedence.
+    pub fn infer_decision_price(mut self, infer: bool) -> Self {
+        self.infer_decision_price = infer;
+        self
+    }
+
+    /// Opt in to rejecting intents whose `limit_price` is more than `bps` basis points away from
+    /// the `decision_price`, which usually indicates a bug. Only checked when both are set.
+    pub fn limit_band_bps(mut self, bps: u32) -> Self {
+        self.limit_band_bps = Some(bps);
+        self
+    }
+
+    /// Set the `limit_price` at build time to `offset_bps` basis points away from the
+    /// `decision_price`: above it for long amounts and below it for short ones, so positive
+    /// offsets are more aggressive. Overrides any explicit `limit_price`, and `build` fails if no
+    /// decision price is set or the resulting limit price isn't positive.
+    pub fn limit_from_decision(mut self, offset_bps: i32) -> Self {
+        self.limit_offset_bps = Some(offset_bps);
+        self
+    }
+
+    pub fn limit_price(mut self, limit_price: impl Into<Decimal>) -> Self {
+        self.limit_price = Some(limit_price.into());
         self
     }
 
-    pub fn limit_price(mut self, limit_price: Decimal) -> Self {
-        self.limit_price = Some(limit_price);
+    pub fn stop_price(mut self, stop_price: impl Into<Decimal>) -> Self {
+        self.stop_price = Some(stop_price.into());
         self
     }
 
-    pub fn stop_price(mut self, stop_price: Decimal) -> Self {
-        self.stop_price = Some(stop_price);
+    pub fn with_bracket(
+        mut self,
+        take_profit: impl Into<Decimal>,
+        stop_loss: impl Into<Decimal>,
+    ) -> Self {
+        self.take_profit = Some(take_profit.into());
+        self.stop_loss = Some(stop_loss.into());
         self
     }
 
@@ -115,23 +682,71 @@ impl PositionIntentBuilder {
         self
     }
 
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// Set the `idempotency_key` to the hex-encoded [`PositionIntent::dedup_key`] of the built
+    /// intent, unless a key was set explicitly.
+    pub fn if_not_exists(mut self) -> Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
     pub fn update_policy(mut self, policy: UpdatePolicy) -> Self {
         self.update_policy = policy;
         self
     }
 
+    pub fn lifetime(mut self, lifetime: IntentLifetime) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn build(self) -> Result<PositionIntent, Error> {
-        if let Some((before, after)) = self.before.zip(self.after) {
-            if before < after {
-                return Err(Error::InvalidBeforeAfter(before, after));
+        self.build_all_errors()
+            .map_err(|mut errors| errors.remove(0))
+    }
+
+    /// Like [`PositionIntentBuilder::build`], but reports every validation failure rather than
+    /// only the first.
+    pub fn build_all_errors(mut self) -> Result<PositionIntent, Vec<Error>> {
+        if self.infer_decision_price && self.decision_price.is_none() {
+            self.decision_price = self.limit_price;
+        }
+        let mut errors = Vec::new();
+        if let Some(bps) = self.limit_offset_bps {
+            match self.decision_price {
+                Some(decision) => {
+                    let offset = Decimal::from(bps) / Decimal::from(10_000) * self.amount.signum();
+                    match (Decimal::ONE + offset).checked_mul(decision) {
+                        Some(limit) if limit > Decimal::ZERO => self.limit_price = Some(limit),
+                        Some(limit) => errors.push(Error::InvalidPrice(limit)),
+                        None => errors.push(Error::InvalidPrice(decision)),
+                    }
+                }
+                None => errors.push(Error::MissingDecisionPrice),
             }
         }
-        match (self.identifier.clone(), self.amount.clone()) {
-            (Identifier::All, Amount::Dollars(_)) => return Err(Error::InvalidCombination),
-            (Identifier::All, Amount::Shares(_)) => return Err(Error::InvalidCombination),
-            _ => (),
+        if let (Some(mult), Amount::Dollars(_)) = (self.leverage, &self.amount) {
+            match self.amount.checked_scale_by(mult) {
+                Ok(amount) => self.amount = amount,
+                Err(error) => errors.push(error),
+            }
         }
-        Ok(PositionIntent {
+        let mut intent = PositionIntent {
+            version: crate::CURRENT_VERSION,
             id: Uuid::new_v4(),
             strategy: self.strategy,
             sub_strategy: self.sub_strategy,
@@ -139,43 +754,122 @@ impl PositionIntentBuilder {
             identifier: self.identifier,
             amount: self.amount,
             update_policy: self.update_policy,
+            lifetime: self.lifetime,
+            priority: self.priority,
             decision_price: self.decision_price,
+            signal: self.signal,
             limit_price: self.limit_price,
             stop_price: self.stop_price,
+            take_profit: self.take_profit,
+            stop_loss: self.stop_loss,
             before: self.before,
             after: self.after,
-        })
+            tags: self.tags,
+            idempotency_key: self.idempotency_key,
+        };
+        if self.if_not_exists && intent.idempotency_key.is_none() {
+            intent.idempotency_key = Some(format!("{:016x}", intent.dedup_key()));
+        }
+        errors.extend(intent.validation_errors());
+        if let (Some(bps), Some(limit), Some(decision)) = (
+            self.limit_band_bps,
+            intent.limit_price,
+            intent.decision_price,
+        ) {
+            let band = decision
+                .abs()
+                .checked_mul(Decimal::from(bps))
+                .map(|band| band / Decimal::from(10_000));
+            let distance = limit.checked_sub(decision).map(|distance| distance.abs());
+            match (band, distance) {
+                (Some(band), Some(distance)) if distance <= band => {}
+                _ => errors.push(Error::LimitOutsideBand(limit, decision, bps)),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(intent)
     }
 }
 
+/// A field that differs between an intent and the intent it supersedes, with display values
+/// for both. Unset optional fields are displayed as `none`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PositionIntent {
+    /// Protocol version the intent was produced with. Intents without a version are treated as
+    /// the current version, and unsupported versions are rejected when deserializing.
+    #[serde(
+        default = "crate::version::current_version",
+        deserialize_with = "crate::version::supported_version"
+    )]
+    pub version: u8,
     pub id: Uuid,
     /// The strategy that is requesting a position. Dollar limits are shared between all positions
     /// of the same strategy.
+    #[serde(deserialize_with = "crate::limits::bounded_string")]
     pub strategy: String,
     /// Identifier for a specific leg of a position for a strategy. Sub-strategies must still
     /// adhere to the dollar limits of the strategy, but the order-manager will keep track of the
     /// holdings at the sub-strategy level.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "crate::limits::bounded_option_string",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub sub_strategy: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub identifier: Identifier,
+    /// Omitted from the serialized intent when it is `Amount::Zero`, and defaults to
+    /// `Amount::Zero` when missing. Consumers that require the field must be on a version that
+    /// applies this default. `Dollars(0)` and `Shares(0)` are always serialized.
+    #[serde(default, skip_serializing_if = "is_zero_variant")]
     pub amount: Amount,
+    /// Defaults to `Update` so that intents serialized before this field existed can still be
+    /// deserialized.
+    #[serde(default)]
     pub update_policy: UpdatePolicy,
+    /// Whether the order-manager should cancel this intent at session close.
+    #[serde(default)]
+    pub lifetime: IntentLifetime,
+    #[serde(default)]
+    pub priority: Priority,
     /// The price at which the decision was made to send a position request. This can be used by
     /// other parts of the app for execution analysis. This field might also be used for
     /// translating between dollars and shares by the order-manager.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decision_price: Option<Decimal>,
+    /// Normalized strength of the signal that produced the intent, for execution analysis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signal: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit_price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_price: Option<Decimal>,
+    /// Exit price at which the order-manager should take profits on the position. Together with
+    /// `stop_loss` this forms a bracket that is submitted as one-cancels-other child orders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub before: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub after: Option<DateTime<Utc>>,
+    /// Free-form labels attached to the intent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// Marks the intent as create-if-absent: the order-manager drops intents whose key it has
+    /// already seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
 }
 
 impl PositionIntent {
@@ -189,42 +883,803 @@ impl PositionIntent {
             sub_strategy: None,
             identifier: identifier.into(),
             amount,
-            update_policy: UpdatePolicy::Update,
+            update_policy: UpdatePolicy::default(),
+            lifetime: IntentLifetime::default(),
+            priority: Priority::default(),
             decision_price: None,
+            signal: None,
             limit_price: None,
             stop_price: None,
+            take_profit: None,
+            stop_loss: None,
             before: None,
             after: None,
+            tags: BTreeMap::new(),
+            idempotency_key: None,
+            if_not_exists: false,
+            infer_decision_price: false,
+            leverage: None,
+            limit_band_bps: None,
+            limit_offset_bps: None,
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use chrono::Duration;
+    /// A builder pre-populated with the fields of this intent, for creating a modified copy. The
+    /// `id` and `timestamp` are not carried over and are regenerated by `build`, and neither is
+    /// the `idempotency_key`, since the copy is a different request.
+    pub fn to_builder(&self) -> PositionIntentBuilder {
+        PositionIntentBuilder {
+            strategy: self.strategy.clone(),
+            sub_strategy: self.sub_strategy.clone(),
+            identifier: self.identifier.clone(),
+            amount: self.amount.clone(),
+            update_policy: self.update_policy.clone(),
+            lifetime: self.lifetime.clone(),
+            priority: self.priority,
+            decision_price: self.decision_price,
+            signal: self.signal,
+            limit_price: self.limit_price,
+            stop_price: self.stop_price,
+            take_profit: self.take_profit,
+            stop_loss: self.stop_loss,
+            before: self.before,
+            after: self.after,
+            tags: self.tags.clone(),
+            idempotency_key: None,
+            if_not_exists: false,
+            infer_decision_price: false,
+            leverage: None,
+            limit_band_bps: None,
+            limit_offset_bps: None,
+        }
+    }
 
-    #[test]
-    fn can_construct_position_intent() {
-        let builder = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1, 0)));
-        let _intent = builder
-            .sub_strategy("B")
-            .decision_price(Decimal::new(2, 0))
-            .limit_price(Decimal::new(3, 0))
-            .stop_price(Decimal::new(3, 0))
-            .update_policy(UpdatePolicy::Retain)
-            .before(Utc::now() + Duration::hours(1))
-            .after(Utc::now())
-            .build()
-            .unwrap();
+    /// Start building an intent for a single ticker, with the amount set afterwards via
+    /// [`PositionIntentBuilder::dollars`] or [`PositionIntentBuilder::shares`]. If neither is
+    /// called, the amount is `Amount::Zero`.
+    pub fn for_ticker(
+        strategy: impl Into<String>,
+        ticker: impl Into<String>,
+    ) -> PositionIntentBuilder {
+        Self::builder(strategy, Identifier::Ticker(ticker.into()), Amount::Zero)
     }
 
-    #[test]
-    fn can_serialize_and_deserialize() {
-        let builder = PositionIntent::builder("A", "AAPL", Amount::Shares(Decimal::new(1, 0)));
-        let intent = builder
-            .sub_strategy("B")
-            .decision_price(Decimal::new(2, 0))
+    /// Key identifying the strategy and, if present, sub-strategy of the intent, in the form
+    /// `strategy` or `strategy:sub_strategy`.
+    pub fn strategy_key(&self) -> String {
+        match &self.sub_strategy {
+            Some(sub_strategy) => {
+                format!("{}{}{}", self.strategy, STRATEGY_SEPARATOR, sub_strategy)
+            }
+            None => self.strategy.clone(),
+        }
+    }
+
+    /// Order in which intents should be processed: higher priority first, then older intents
+    /// first. Sorting with this comparator puts the most urgent intent at the front.
+    pub fn scheduling_cmp(&self, other: &PositionIntent) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| self.timestamp.cmp(&other.timestamp))
+    }
+
+    /// The changes to amount, prices, update policy and window made by this intent relative to
+    /// `prior`.
+    pub fn diff(&self, prior: &PositionIntent) -> Vec<FieldChange> {
+        fn change<T: PartialEq>(
+            field: &'static str,
+            old: &T,
+            new: &T,
+            display: fn(&T) -> String,
+        ) -> Option<FieldChange> {
+            (old != new).then(|| FieldChange {
+                field,
+                old: display(old),
+                new: display(new),
+            })
+        }
+        fn optional<T: ToString>(x: &Option<T>) -> String {
+            x.as_ref()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| "none".into())
+        }
+        vec![
+            change("amount", &prior.amount, &self.amount, Amount::to_string),
+            change(
+                "update_policy",
+                &prior.update_policy,
+                &self.update_policy,
+                |policy| policy.as_str().into(),
+            ),
+            change(
+                "decision_price",
+                &prior.decision_price,
+                &self.decision_price,
+                optional,
+            ),
+            change(
+                "limit_price",
+                &prior.limit_price,
+                &self.limit_price,
+                optional,
+            ),
+            change("stop_price", &prior.stop_price, &self.stop_price, optional),
+            change(
+                "take_profit",
+                &prior.take_profit,
+                &self.take_profit,
+                optional,
+            ),
+            change("stop_loss", &prior.stop_loss, &self.stop_loss, optional),
+            change("before", &prior.before, &self.before, optional),
+            change("after", &prior.after, &self.after, optional),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Expand an `Identifier::All` intent into one intent per ticker in `tickers`, each with a
+    /// fresh id and no idempotency key. Intents for a single ticker are returned as is.
+    pub fn expand(&self, tickers: &[String]) -> Vec<PositionIntent> {
+        match self.identifier {
+            Identifier::All => tickers
+                .iter()
+                .map(|ticker| PositionIntent {
+                    id: Uuid::new_v4(),
+                    identifier: Identifier::Ticker(ticker.clone()),
+                    idempotency_key: None,
+                    ..self.clone()
+                })
+                .collect(),
+            Identifier::Ticker(_) | Identifier::Asset { .. } => vec![self.clone()],
+        }
+    }
+
+    /// Ladder into the position with `n` child intents, each for an equal share of the amount
+    /// (see [`Amount::split`]) and a fresh id. The first child's limit is the `limit_price`, or
+    /// the `decision_price` if no limit is set, and each following child's limit is `step`
+    /// further along.
+    pub fn scale_in(&self, n: usize, step: Decimal) -> Result<Vec<PositionIntent>, Error> {
+        let start = self
+            .limit_price
+            .or(self.decision_price)
+            .ok_or(Error::MissingDecisionPrice)?;
+        self.amount
+            .split(n)
+            .into_iter()
+            .enumerate()
+            .map(|(i, amount)| {
+                let limit_price = step
+                    .checked_mul(Decimal::from(i))
+                    .and_then(|offset| start.checked_add(offset))
+                    .ok_or(Error::InvalidPrice(step))?;
+                if limit_price <= Decimal::ZERO {
+                    return Err(Error::InvalidPrice(limit_price));
+                }
+                Ok(PositionIntent {
+                    id: Uuid::new_v4(),
+                    amount,
+                    limit_price: Some(limit_price),
+                    idempotency_key: None,
+                    ..self.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Update the fields present in the JSON object `patch`, e.g. `{"limit_price": "101"}`, with
+    /// `null` unsetting optional fields. Only the [`PATCHABLE_FIELDS`] may be patched, and any
+    /// other key, including a misspelled one, is rejected. The patched intent is re-validated,
+    /// and left unchanged if the patch is invalid.
+    pub fn apply_patch(&mut self, patch: &Value) -> Result<(), Error> {
+        let invalid = |reason: String| Error::InvalidPatch(reason);
+        let patch = patch
+            .as_object()
+            .ok_or_else(|| invalid("expected an object".into()))?;
+        let mut object = match serde_json::to_value(&*self) {
+            Ok(Value::Object(object)) => object,
+            _ => unreachable!("PositionIntent always serializes to a JSON object"),
+        };
+        if patch
+            .get("id")
+            .is_some_and(|id| object.get("id") != Some(id))
+        {
+            return Err(invalid("the id of an intent cannot be changed".into()));
+        }
+        if let Some(field) = patch
+            .keys()
+            .find(|field| *field != "id" && !PATCHABLE_FIELDS.contains(&field.as_str()))
+        {
+            return Err(invalid(format!("field `{}` cannot be patched", field)));
+        }
+        for (field, value) in patch {
+            match value {
+                Value::Null => object.remove(field),
+                value => object.insert(field.clone(), value.clone()),
+            };
+        }
+        let patched: PositionIntent =
+            serde_json::from_value(Value::Object(object)).map_err(|e| invalid(e.to_string()))?;
+        patched.validate()?;
+        *self = patched;
+        Ok(())
+    }
+
+    /// Encode the intent as a positional JSON array, for high-throughput feeds. Elements follow
+    /// [`COMPACT_FIELDS`], with the amount split into its `amount_type` and `amount_value` and a
+    /// `Ticker` or `All` identifier written as a bare string. `Asset` identifiers, and tickers
+    /// spelled like the `All` wildcard, keep their tagged form. Unset fields are `null`.
+    pub fn to_compact_array(&self) -> Value {
+        let mut object = match serde_json::to_value(self) {
+            Ok(Value::Object(object)) => object,
+            _ => unreachable!("PositionIntent always serializes to a JSON object"),
+        };
+        match &self.identifier {
+            Identifier::Ticker(ticker) if ticker != "*" && ticker != "all" => {
+                object.insert("identifier".into(), ticker.as_str().into());
+            }
+            Identifier::All => {
+                object.insert("identifier".into(), "*".into());
+            }
+            Identifier::Ticker(_) | Identifier::Asset { .. } => {}
+        }
+        if let Ok(Value::Object(mut amount)) =
+            serde_json::to_value(FlatAmount::from(self.amount.clone()))
+        {
+            for (field, key) in [("amount_type", "type"), ("amount_value", "value")] {
+                if let Some(value) = amount.remove(key) {
+                    object.insert(field.into(), value);
+                }
+            }
+        }
+        COMPACT_FIELDS
+            .iter()
+            .map(|field| object.remove(*field).unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// Decode an intent written by [`PositionIntent::to_compact_array`]. Trailing elements may be
+    /// omitted, in which case those fields take their defaults.
+    pub fn from_compact_array(value: &Value) -> Result<PositionIntent, Error> {
+        let invalid = |reason: String| Error::InvalidCompactArray(reason);
+        let elements = value
+            .as_array()
+            .ok_or_else(|| invalid("expected an array".into()))?;
+        if elements.len() > COMPACT_FIELDS.len() {
+            return Err(invalid(format!(
+                "expected at most {} elements, got {}",
+                COMPACT_FIELDS.len(),
+                elements.len()
+            )));
+        }
+        let mut object: Map<String, Value> = COMPACT_FIELDS
+            .iter()
+            .zip(elements)
+            .filter(|(_, value)| !value.is_null())
+            .map(|(field, value)| (field.to_string(), value.clone()))
+            .collect();
+        let mut amount = Map::new();
+        for (field, key) in [("amount_type", "type"), ("amount_value", "value")] {
+            if let Some(value) = object.remove(field) {
+                amount.insert(key.into(), value);
+            }
+        }
+        if !amount.is_empty() {
+            let amount = Amount::from_value(&Value::Object(amount))?;
+            object.insert(
+                "amount".into(),
+                serde_json::to_value(amount).map_err(|e| invalid(e.to_string()))?,
+            );
+        }
+        serde_json::from_value(Value::Object(object)).map_err(|e| invalid(e.to_string()))
+    }
+
+    /// Number of bytes in the JSON serialization of the intent, computed without allocating the
+    /// serialized string.
+    pub fn serialized_len(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        serde_json::to_writer(&mut counter, self)
+            .expect("PositionIntent always serializes to JSON");
+        counter.0
+    }
+
+    /// Hash of the intent's strategy, sub-strategy, identifier and amount, ignoring `id` and
+    /// `timestamp`. Uses FNV-1a over a canonical encoding, so keys are stable across processes
+    /// and releases and can be persisted.
+    pub fn dedup_key(&self) -> u64 {
+        let identifier = match &self.identifier {
+            Identifier::Ticker(ticker) => format!("ticker:{}", ticker),
+            Identifier::Asset { symbol, class } => format!("asset:{}:{}", class.as_str(), symbol),
+            Identifier::All => "all".into(),
+        };
+        let amount = match self.amount {
+            Amount::Dollars(x) => format!("dollars:{}", x.normalize()),
+            Amount::Shares(x) => format!("shares:{}", x.normalize()),
+            Amount::Zero => "zero".into(),
+        };
+        let sub_strategy = self.sub_strategy.as_deref().unwrap_or_default();
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for part in [self.strategy.as_str(), sub_strategy, &identifier, &amount] {
+            for byte in part.bytes().chain(std::iter::once(0)) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    /// The order type implied by the intent's prices: `StopLimit` when both `stop_price` and
+    /// `limit_price` are set, `Stop` or `Limit` when only one is, and `Market` otherwise.
+    pub fn derive_order_type(&self) -> OrderType {
+        match (self.stop_price, self.limit_price) {
+            (Some(stop_price), Some(limit_price)) => OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            },
+            (Some(stop_price), None) => OrderType::Stop { stop_price },
+            (None, Some(limit_price)) => OrderType::Limit { limit_price },
+            (None, None) => OrderType::Market,
+        }
+    }
+
+    /// Whether the intent may be acted on at `now`, i.e. `after` is unset or has passed.
+    pub fn is_ready(&self, now: DateTime<Utc>) -> bool {
+        self.after.is_none_or(|after| now >= after)
+    }
+
+    /// How long the intent has been active at `now`, i.e. `now - after`. `None` if `after` is
+    /// unset or hasn't been reached yet.
+    pub fn active_duration(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.after
+            .filter(|after| now >= *after)
+            .map(|after| now - after)
+    }
+
+    /// Whether the intent's `before` deadline has passed at `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.before.is_some_and(|before| now > before)
+    }
+
+    /// Whether the intent is denominated in dollars or shares, or is a variant-agnostic `Zero`.
+    pub fn amount_unit(&self) -> AmountUnit {
+        self.amount.unit()
+    }
+
+    /// Whether reaching the target from a holding of `current_shares` requires buying or
+    /// selling, with `Dollars` targets converted at the `decision_price`. `None` if no trade is
+    /// needed. A `Dollars` target without a positive decision price is an error, since the side
+    /// can't be computed. The update policy isn't taken into account.
+    pub fn implied_side(&self, current_shares: Decimal) -> Result<Option<Side>, Error> {
+        let target = match self.amount {
+            Amount::Shares(x) => x,
+            Amount::Dollars(x) => {
+                let price = self.decision_price.ok_or(Error::MissingDecisionPrice)?;
+                if price <= Decimal::ZERO {
+                    return Err(Error::InvalidPrice(price));
+                }
+                x.checked_div(price).ok_or(Error::InvalidPrice(price))?
+            }
+            Amount::Zero => Decimal::ZERO,
+        };
+        Ok(match target.cmp(&current_shares) {
+            Ordering::Greater => Some(Side::Buy),
+            Ordering::Less => Some(Side::Sell),
+            Ordering::Equal => None,
+        })
+    }
+
+    /// Re-express a `Dollars` amount in shares at `decision_price`, keeping the exact quotient so
+    /// the target exposure is unchanged. Use [`Amount::convert_to_shares`] to round to a venue's
+    /// precision. `Shares` and `Zero` amounts are returned unchanged, and a `Dollars` amount
+    /// without a decision price is an error.
+    pub fn in_shares(mut self) -> Result<PositionIntent, Error> {
+        if let Amount::Dollars(dollars) = self.amount {
+            let price = self.decision_price.ok_or(Error::MissingDecisionPrice)?;
+            if price <= Decimal::ZERO {
+                return Err(Error::InvalidPrice(price));
+            }
+            let shares = dollars
+                .checked_div(price)
+                .ok_or(Error::InvalidPrice(price))?;
+            self.amount = Amount::Shares(shares);
+        }
+        Ok(self)
+    }
+
+    /// A new intent flattening the position targeted by this one: same strategy, sub-strategy and
+    /// identifier, with `Amount::Zero` and an `Update` policy.
+    pub fn closing_intent(&self) -> Result<PositionIntent, Error> {
+        let mut builder =
+            PositionIntent::builder(self.strategy.clone(), self.identifier.clone(), Amount::Zero)
+                .update_policy(UpdatePolicy::Update);
+        if let Some(sub_strategy) = &self.sub_strategy {
+            builder = builder.sub_strategy(sub_strategy.clone());
+        }
+        builder.build()
+    }
+
+    /// Check every invariant that [`PositionIntentBuilder::build`] enforces. Useful for intents
+    /// that were deserialized rather than built.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn validation_errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        let names = std::iter::once(&self.strategy).chain(self.sub_strategy.as_ref());
+        for name in names {
+            if name.is_empty() || name.contains(STRATEGY_SEPARATOR) {
+                errors.push(Error::InvalidStrategyName(name.clone()));
+            }
+        }
+        if let Some((before, after)) = self.before.zip(self.after) {
+            if before < after {
+                errors.push(Error::InvalidBeforeAfter(before, after));
+            }
+        }
+        match (&self.identifier, &self.amount) {
+            (Identifier::All, Amount::Dollars(_)) => errors.push(Error::InvalidCombination),
+            (Identifier::All, Amount::Shares(_)) => errors.push(Error::InvalidCombination),
+            _ => (),
+        }
+        // Flattening everything while retaining only one direction is ambiguous: it's unclear
+        // whether positions on the other side are meant to be closed or left alone.
+        if let (Identifier::All, UpdatePolicy::RetainLong | UpdatePolicy::RetainShort) =
+            (&self.identifier, &self.update_policy)
+        {
+            errors.push(Error::InvalidAllPolicy(self.update_policy.clone()));
+        }
+        let contradictory = match self.update_policy {
+            UpdatePolicy::RetainShort => self.amount.is_sign_positive() && !self.amount.is_zero(),
+            UpdatePolicy::RetainLong => self.amount.is_sign_negative() && !self.amount.is_zero(),
+            _ => false,
+        };
+        if contradictory {
+            errors.push(Error::InvalidPolicyAmount(
+                self.update_policy.clone(),
+                self.amount.clone(),
+            ));
+        }
+        if let (Some(tp), Some(sl), Some(price)) =
+            (self.take_profit, self.stop_loss, self.decision_price)
+        {
+            let valid = if self.amount.is_sign_positive() {
+                sl < price && price < tp
+            } else if self.amount.is_sign_negative() {
+                tp < price && price < sl
+            } else {
+                true
+            };
+            if !valid {
+                errors.push(Error::InvalidBracket(tp, sl, price));
+            }
+        }
+        errors
+    }
+
+    /// Break the intent into its `(strategy, sub_strategy, identifier, amount)`.
+    pub fn into_parts(self) -> (String, Option<String>, Identifier, Amount) {
+        (
+            self.strategy,
+            self.sub_strategy,
+            self.identifier,
+            self.amount,
+        )
+    }
+
+    /// Column names matching the fields of [`PositionIntent::to_csv_record`].
+    pub fn csv_headers() -> Vec<&'static str> {
+        vec![
+            "id",
+            "strategy",
+            "sub_strategy",
+            "timestamp",
+            "ticker",
+            "amount_type",
+            "amount_value",
+            "update_policy",
+            "decision_price",
+            "limit_price",
+            "stop_price",
+            "take_profit",
+            "stop_loss",
+            "before",
+            "after",
+        ]
+    }
+
+    /// Flatten the intent into a CSV record. Unset optional fields are left empty,
+    /// `Identifier::All` is written as `*` and `Amount::Zero` as type `zero` with value `0`.
+    pub fn to_csv_record(&self) -> Vec<String> {
+        let optional = |x: Option<Decimal>| x.map(|x| x.to_string()).unwrap_or_default();
+        let optional_time =
+            |x: Option<DateTime<Utc>>| x.map(|x| x.to_rfc3339()).unwrap_or_default();
+        let ticker = self.identifier.symbol().unwrap_or("*").to_string();
+        let (amount_type, amount_value) = match self.amount {
+            Amount::Dollars(x) => ("dollars", x),
+            Amount::Shares(x) => ("shares", x),
+            Amount::Zero => ("zero", Decimal::ZERO),
+        };
+        vec![
+            self.id.to_string(),
+            self.strategy.clone(),
+            self.sub_strategy.clone().unwrap_or_default(),
+            self.timestamp.to_rfc3339(),
+            ticker,
+            amount_type.into(),
+            amount_value.to_string(),
+            self.update_policy.as_str().into(),
+            optional(self.decision_price),
+            optional(self.limit_price),
+            optional(self.stop_price),
+            optional(self.take_profit),
+            optional(self.stop_loss),
+            optional_time(self.before),
+            optional_time(self.after),
+        ]
+    }
+}
+
+/// Fields that [`PositionIntent::apply_patch`] may change. The `id` may also appear in a patch,
+/// but only with its current value.
+pub const PATCHABLE_FIELDS: [&str; 9] = [
+    "amount",
+    "update_policy",
+    "decision_price",
+    "limit_price",
+    "stop_price",
+    "take_profit",
+    "stop_loss",
+    "before",
+    "after",
+];
+
+/// Order of the elements in [`PositionIntent::to_compact_array`]. New fields are only ever
+/// appended, so older arrays remain decodable.
+pub const COMPACT_FIELDS: [&str; 21] = [
+    "version",
+    "id",
+    "strategy",
+    "sub_strategy",
+    "timestamp",
+    "identifier",
+    "amount_type",
+    "amount_value",
+    "update_policy",
+    "lifetime",
+    "priority",
+    "decision_price",
+    "limit_price",
+    "stop_price",
+    "take_profit",
+    "stop_loss",
+    "before",
+    "after",
+    "tags",
+    "idempotency_key",
+    "signal",
+];
+
+/// Separates the strategy from the sub-strategy in [`PositionIntent::strategy_key`], so neither
+/// name may contain it.
+const STRATEGY_SEPARATOR: char = ':';
+
+fn is_zero_variant(amount: &Amount) -> bool {
+    matches!(amount, Amount::Zero)
+}
+
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes only the fields of an intent that the risk service needs: `id`, `strategy`,
+/// `timestamp`, `identifier`, `amount` and `update_policy`.
+#[derive(Debug, Clone, Copy)]
+pub struct RiskView<'a>(pub &'a PositionIntent);
+
+#[derive(Serialize)]
+struct RiskFields<'a> {
+    id: &'a Uuid,
+    strategy: &'a str,
+    timestamp: &'a DateTime<Utc>,
+    identifier: &'a Identifier,
+    amount: &'a Amount,
+    update_policy: &'a UpdatePolicy,
+}
+
+impl Serialize for RiskView<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let intent = self.0;
+        RiskFields {
+            id: &intent.id,
+            strategy: &intent.strategy,
+            timestamp: &intent.timestamp,
+            identifier: &intent.identifier,
+            amount: &intent.amount,
+            update_policy: &intent.update_policy,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// One-line summary such as `AAPL target $1000 (strategy=momentum, policy=update)`. `All`
+/// intents are shown as `ALL`, and `Zero` amounts as `flatten`.
+impl fmt::Display for PositionIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.identifier.symbol().unwrap_or("ALL"))?;
+        match self.amount {
+            Amount::Zero => write!(f, " flatten")?,
+            ref amount => write!(f, " target {}", amount)?,
+        }
+        write!(
+            f,
+            " (strategy={}, policy={})",
+            self.strategy_key(),
+            self.update_policy.as_str()
+        )
+    }
+}
+
+/// Total `(dollars, shares)` across `amounts`, summing each variant separately. `Zero` amounts
+/// contribute to neither. Returns an error if either total overflows.
+pub fn aggregate(amounts: &[Amount]) -> Result<(Decimal, Decimal), Error> {
+    amounts.iter().try_fold(
+        (Decimal::ZERO, Decimal::ZERO),
+        |(dollars, shares), amount| {
+            let overflow = |total| Error::AmountOverflow(amount.clone(), total);
+            Ok(match amount {
+                Amount::Dollars(x) => (
+                    dollars.checked_add(*x).ok_or_else(|| overflow(dollars))?,
+                    shares,
+                ),
+                Amount::Shares(x) => (
+                    dollars,
+                    shares.checked_add(*x).ok_or_else(|| overflow(shares))?,
+                ),
+                Amount::Zero => (dollars, shares),
+            })
+        },
+    )
+}
+
+/// Bucket intents by `strategy`, preserving their relative order.
+pub fn group_by_strategy(intents: Vec<PositionIntent>) -> HashMap<String, Vec<PositionIntent>> {
+    let mut groups: HashMap<String, Vec<PositionIntent>> = HashMap::new();
+    for intent in intents {
+        groups
+            .entry(intent.strategy.clone())
+            .or_default()
+            .push(intent);
+    }
+    groups
+}
+
+/// Bucket intents by [`PositionIntent::strategy_key`], preserving their relative order.
+pub fn group_by_strategy_key(intents: Vec<PositionIntent>) -> HashMap<String, Vec<PositionIntent>> {
+    let mut groups: HashMap<String, Vec<PositionIntent>> = HashMap::new();
+    for intent in intents {
+        groups
+            .entry(intent.strategy_key())
+            .or_default()
+            .push(intent);
+    }
+    groups
+}
+
+/// Check that the gross dollar exposure targeted by each strategy in `intents` is within its
+/// cap in `caps`. `Dollars` amounts count at their absolute value, `Shares` amounts are converted
+/// at their `decision_price` and ignored if it is unset, and strategies without a cap are
+/// unrestricted. A total too large to represent counts as exceeding any cap, reported as
+/// `Decimal::MAX`.
+pub fn validate_strategy_cap(
+    intents: &[PositionIntent],
+    caps: &HashMap<String, Decimal>,
+) -> Result<(), Error> {
+    let mut totals: BTreeMap<&str, Decimal> = BTreeMap::new();
+    for intent in intents {
+        let dollars = match (&intent.amount, intent.decision_price) {
+            (Amount::Dollars(x), _) => x.abs(),
+            (Amount::Shares(x), Some(price)) => x
+                .checked_mul(price)
+                .ok_or_else(|| Error::AmountOverflow(intent.amount.clone(), price))?
+                .abs(),
+            (Amount::Shares(_), None) | (Amount::Zero, _) => continue,
+        };
+        let total = totals.entry(&intent.strategy).or_default();
+        *total = total.checked_add(dollars).unwrap_or(Decimal::MAX);
+    }
+    for (strategy, total) in totals {
+        if let Some(&cap) = caps.get(strategy) {
+            if total > cap {
+                return Err(Error::StrategyCapExceeded(strategy.to_string(), total, cap));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keep only the newest intent per strategy and identifier, dropping older duplicates. The
+/// surviving intents keep their relative order, and on equal timestamps the later one wins.
+pub fn latest_per_identifier(intents: Vec<PositionIntent>) -> Vec<PositionIntent> {
+    let mut latest: HashMap<(String, Identifier), usize> = HashMap::new();
+    for (i, intent) in intents.iter().enumerate() {
+        let key = (intent.strategy.clone(), intent.identifier.clone());
+        match latest.get(&key) {
+            Some(&j) if intents[j].timestamp > intent.timestamp => (),
+            _ => {
+                latest.insert(key, i);
+            }
+        }
+    }
+    let mut keep = vec![false; intents.len()];
+    for i in latest.into_values() {
+        keep[i] = true;
+    }
+    intents
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(intent, keep)| keep.then_some(intent))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_construct_position_intent() {
+        let builder = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1, 0)));
+        let _intent = builder
+            .sub_strategy("B")
+            .decision_price(Decimal::new(2, 0))
+            .limit_price(Decimal::new(3, 0))
+            .stop_price(Decimal::new(3, 0))
+            .update_policy(UpdatePolicy::Retain)
+            .before(Utc::now() + Duration::hours(1))
+            .after(Utc::now())
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn can_construct_with_amount_shortcuts() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(100)
+            .build()
+            .unwrap();
+        assert_eq!(intent.identifier, Identifier::Ticker("AAPL".into()));
+        assert_eq!(intent.amount, Amount::Dollars(Decimal::new(100, 0)));
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(Decimal::new(15, 1))
+            .build()
+            .unwrap();
+        assert_eq!(intent.amount, Amount::Shares(Decimal::new(15, 1)));
+
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert_eq!(intent.amount, Amount::Zero);
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize() {
+        let builder = PositionIntent::builder("A", "AAPL", Amount::Shares(Decimal::new(1, 0)));
+        let intent = builder
+            .sub_strategy("B")
+            .decision_price(Decimal::new(2, 0))
             .limit_price(Decimal::new(3, 0))
             .stop_price(Decimal::new(3, 0))
             .update_policy(UpdatePolicy::Retain)
@@ -236,4 +1691,1615 @@ mod test {
         let deserialized = serde_json::from_str(&serialized).unwrap();
         assert_eq!(intent, deserialized);
     }
+
+    #[test]
+    fn lifetime_defaults_to_persistent() {
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert_eq!(intent.lifetime, IntentLifetime::Persistent);
+
+        let mut value = serde_json::to_value(&intent).unwrap();
+        value.as_object_mut().unwrap().remove("lifetime");
+        let deserialized: PositionIntent = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.lifetime, IntentLifetime::Persistent);
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .lifetime(IntentLifetime::Day)
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_string(&intent).unwrap();
+        let deserialized: PositionIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, intent);
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_flat_amount() {
+        let amounts = vec![
+            (
+                Amount::Dollars(Decimal::new(1005, 1)),
+                r#"{"type":"dollars","value":"100.5"}"#,
+            ),
+            (
+                Amount::Shares(Decimal::new(100, 0)),
+                r#"{"type":"shares","value":"100"}"#,
+            ),
+            (Amount::Zero, r#"{"type":"zero"}"#),
+        ];
+        for (amount, expected) in amounts {
+            let serialized = serde_json::to_string(&FlatAmount::from(amount.clone())).unwrap();
+            assert_eq!(serialized, expected);
+            let deserialized: FlatAmount = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(Amount::from(deserialized), amount);
+        }
+    }
+
+    #[test]
+    fn bracket_must_surround_decision_price() {
+        let long = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .decision_price(Decimal::new(100, 0));
+        assert!(long
+            .clone()
+            .with_bracket(Decimal::new(110, 0), Decimal::new(95, 0))
+            .build()
+            .is_ok());
+        assert!(matches!(
+            long.with_bracket(Decimal::new(95, 0), Decimal::new(110, 0))
+                .build(),
+            Err(Error::InvalidBracket(_, _, _))
+        ));
+
+        let short = PositionIntent::for_ticker("A", "AAPL")
+            .shares(-10)
+            .decision_price(Decimal::new(100, 0));
+        assert!(short
+            .clone()
+            .with_bracket(Decimal::new(95, 0), Decimal::new(110, 0))
+            .build()
+            .is_ok());
+        assert!(matches!(
+            short
+                .with_bracket(Decimal::new(110, 0), Decimal::new(95, 0))
+                .build(),
+            Err(Error::InvalidBracket(_, _, _))
+        ));
+
+        // Without a decision price there is nothing to validate against
+        assert!(PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .with_bracket(Decimal::new(95, 0), Decimal::new(110, 0))
+            .build()
+            .is_ok());
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .decision_price(Decimal::new(100, 0))
+            .with_bracket(Decimal::new(110, 0), Decimal::new(95, 0))
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_string(&intent).unwrap();
+        let deserialized: PositionIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, intent);
+    }
+
+    #[test]
+    fn can_destructure_into_parts() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .sub_strategy("B")
+            .shares(10)
+            .build()
+            .unwrap();
+        let (strategy, sub_strategy, identifier, amount) = intent.into_parts();
+        assert_eq!(strategy, "A");
+        assert_eq!(sub_strategy, Some("B".into()));
+        assert_eq!(identifier, Identifier::Ticker("AAPL".into()));
+        assert_eq!(amount, Amount::Shares(Decimal::new(10, 0)));
+    }
+
+    #[test]
+    fn can_deserialize_update_policy_aliases() {
+        let policy: UpdatePolicy = serde_json::from_str(r#""hold""#).unwrap();
+        assert_eq!(policy, UpdatePolicy::Retain);
+        let policy: UpdatePolicy = serde_json::from_str(r#""replace""#).unwrap();
+        assert_eq!(policy, UpdatePolicy::Update);
+        assert_eq!(
+            serde_json::to_string(&UpdatePolicy::Update).unwrap(),
+            r#""update""#
+        );
+    }
+
+    #[test]
+    fn can_group_by_strategy() {
+        let intents = vec![
+            PositionIntent::for_ticker("A", "AAPL").build().unwrap(),
+            PositionIntent::for_ticker("A", "MSFT")
+                .sub_strategy("X")
+                .build()
+                .unwrap(),
+            PositionIntent::for_ticker("B", "AAPL").build().unwrap(),
+            PositionIntent::for_ticker("A", "TSLA")
+                .sub_strategy("X")
+                .build()
+                .unwrap(),
+        ];
+
+        let groups = group_by_strategy(intents.clone());
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["A"].len(), 3);
+        assert_eq!(groups["B"].len(), 1);
+
+        let groups = group_by_strategy_key(intents);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups["A"].len(), 1);
+        assert_eq!(groups["A:X"].len(), 2);
+        assert_eq!(
+            groups["A:X"][1].identifier,
+            Identifier::Ticker("TSLA".into())
+        );
+        assert_eq!(groups["B"].len(), 1);
+    }
+
+    #[test]
+    fn can_display_amount() {
+        assert_eq!(Amount::Dollars(Decimal::new(1050, 1)).to_string(), "$105.0");
+        assert_eq!(
+            Amount::Dollars(Decimal::new(-1050, 1)).to_string(),
+            "-$105.0"
+        );
+        assert_eq!(Amount::Shares(Decimal::new(100, 0)).to_string(), "100 sh");
+        assert_eq!(Amount::Shares(Decimal::new(-100, 0)).to_string(), "-100 sh");
+        assert_eq!(Amount::Zero.to_string(), "0");
+    }
+
+    #[test]
+    fn can_deserialize_legacy_layout() {
+        let data = r#"{
+            "id": "7b2c5f2c-5c1e-4f5b-8d3a-6a8f1b7e9c10",
+            "strategy": "A",
+            "timestamp": "2021-06-01T14:30:00Z",
+            "identifier": {"ticker": "AAPL"},
+            "amount": {"dollars": "100"}
+        }"#;
+        let intent: PositionIntent = serde_json::from_str(data).unwrap();
+        assert_eq!(intent.update_policy, UpdatePolicy::Update);
+        assert_eq!(intent.lifetime, IntentLifetime::Persistent);
+        assert_eq!(intent.amount, Amount::Dollars(Decimal::new(100, 0)));
+    }
+
+    #[test]
+    fn can_deserialize_bare_identifier() {
+        let identifier: Identifier = serde_json::from_str(r#""AAPL""#).unwrap();
+        assert_eq!(identifier, Identifier::Ticker("AAPL".into()));
+        let identifier: Identifier = serde_json::from_str(r#"{"ticker":"AAPL"}"#).unwrap();
+        assert_eq!(identifier, Identifier::Ticker("AAPL".into()));
+        let identifier: Identifier = serde_json::from_str(r#""*""#).unwrap();
+        assert_eq!(identifier, Identifier::All);
+        let identifier: Identifier = serde_json::from_str(r#""all""#).unwrap();
+        assert_eq!(identifier, Identifier::All);
+        assert_eq!(
+            serde_json::to_string(&Identifier::Ticker("AAPL".into())).unwrap(),
+            r#"{"ticker":"AAPL"}"#
+        );
+    }
+
+    #[test]
+    fn identifier_reports_length_limit() {
+        let _lock = crate::limits::TEST_LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let over_limit = "A".repeat(crate::DEFAULT_MAX_STRING_LENGTH + 1);
+        for data in [
+            format!(r#""{}""#, over_limit),
+            format!(r#"{{"ticker":"{}"}}"#, over_limit),
+        ] {
+            let error = serde_json::from_str::<Identifier>(&data).unwrap_err();
+            assert!(error.to_string().contains("exceeds maximum length"));
+        }
+        assert!(serde_json::from_str::<Identifier>("1").is_err());
+    }
+
+    #[test]
+    fn policy_must_agree_with_amount_direction() {
+        let build = |shares: i64, policy: UpdatePolicy| {
+            PositionIntent::for_ticker("A", "AAPL")
+                .shares(shares)
+                .update_policy(policy)
+                .build()
+        };
+        assert!(matches!(
+            build(10, UpdatePolicy::RetainShort),
+            Err(Error::InvalidPolicyAmount(UpdatePolicy::RetainShort, _))
+        ));
+        assert!(matches!(
+            build(-10, UpdatePolicy::RetainLong),
+            Err(Error::InvalidPolicyAmount(UpdatePolicy::RetainLong, _))
+        ));
+        assert!(build(-10, UpdatePolicy::RetainShort).is_ok());
+        assert!(build(10, UpdatePolicy::RetainLong).is_ok());
+        assert!(build(0, UpdatePolicy::RetainShort).is_ok());
+        assert!(PositionIntent::for_ticker("A", "AAPL")
+            .update_policy(UpdatePolicy::RetainLong)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn can_compute_signum() {
+        assert_eq!(Amount::Dollars(Decimal::new(150, 1)).signum(), Decimal::ONE);
+        assert_eq!(Amount::Shares(Decimal::new(-3, 0)).signum(), -Decimal::ONE);
+        assert_eq!(Amount::Shares(Decimal::ZERO).signum(), Decimal::ZERO);
+        assert_eq!(Amount::Zero.signum(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn can_infer_decision_price_from_limit() {
+        let builder = PositionIntent::for_ticker("A", "AAPL").limit_price(Decimal::new(100, 0));
+        let intent = builder.clone().build().unwrap();
+        assert_eq!(intent.decision_price, None);
+
+        let intent = builder.clone().infer_decision_price(true).build().unwrap();
+        assert_eq!(intent.decision_price, Some(Decimal::new(100, 0)));
+
+        let intent = builder
+            .decision_price(Decimal::new(99, 0))
+            .infer_decision_price(true)
+            .build()
+            .unwrap();
+        assert_eq!(intent.decision_price, Some(Decimal::new(99, 0)));
+    }
+
+    #[test]
+    fn csv_record_matches_headers() {
+        let headers = PositionIntent::csv_headers();
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(100)
+            .limit_price(Decimal::new(1005, 1))
+            .build()
+            .unwrap();
+        let record = intent.to_csv_record();
+        assert_eq!(record.len(), headers.len());
+        assert_eq!(record[4], "AAPL");
+        assert_eq!(record[5], "dollars");
+        assert_eq!(record[6], "100");
+        assert_eq!(record[9], "100.5");
+        assert_eq!(record[10], "");
+
+        let intent = PositionIntent::builder("A", Identifier::All, Amount::Zero)
+            .build()
+            .unwrap();
+        let record = intent.to_csv_record();
+        assert_eq!(record.len(), headers.len());
+        assert_eq!(record[4], "*");
+        assert_eq!(record[5], "zero");
+        assert_eq!(record[6], "0");
+    }
+
+    #[test]
+    fn can_clamp_amount() {
+        let min = Amount::Dollars(Decimal::new(-100, 0));
+        let max = Amount::Dollars(Decimal::new(100, 0));
+        let clamp = |x: i64| {
+            Amount::Dollars(Decimal::new(x, 0))
+                .clamp(min.clone(), max.clone())
+                .unwrap()
+        };
+        assert_eq!(clamp(-500), min);
+        assert_eq!(clamp(50), Amount::Dollars(Decimal::new(50, 0)));
+        assert_eq!(clamp(500), max);
+        assert_eq!(
+            Amount::Zero
+                .clamp(Amount::Dollars(Decimal::new(10, 0)), max.clone())
+                .unwrap(),
+            Amount::Dollars(Decimal::new(10, 0))
+        );
+        assert!(matches!(
+            Amount::Shares(Decimal::new(5, 0)).clamp(min.clone(), max.clone()),
+            Err(Error::IncompatibleAmountError(_, _))
+        ));
+        assert!(matches!(
+            Amount::Dollars(Decimal::new(5, 0)).clamp(max, min),
+            Err(Error::InvalidBounds(_, _))
+        ));
+    }
+
+    #[test]
+    fn can_collect_all_build_errors() {
+        let now = Utc::now();
+        let errors = PositionIntent::builder("A", Identifier::All, Amount::Dollars(Decimal::ONE))
+            .before(now)
+            .after(now + Duration::hours(1))
+            .build_all_errors()
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], Error::InvalidBeforeAfter(_, _)));
+        assert!(matches!(errors[1], Error::InvalidCombination));
+
+        let errors = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .limit_from_decision(50)
+            .before(now)
+            .after(now + Duration::hours(1))
+            .build_all_errors()
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], Error::MissingDecisionPrice));
+        assert!(matches!(errors[1], Error::InvalidBeforeAfter(_, _)));
+    }
+
+    #[test]
+    fn price_setters_accept_literals() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .decision_price(100)
+            .limit_price(Decimal::new(1015, 1))
+            .stop_price(99u8)
+            .with_bracket(110, 95)
+            .build()
+            .unwrap();
+        assert_eq!(intent.decision_price, Some(Decimal::new(100, 0)));
+        assert_eq!(intent.limit_price, Some(Decimal::new(1015, 1)));
+        assert_eq!(intent.stop_price, Some(Decimal::new(99, 0)));
+        assert_eq!(intent.take_profit, Some(Decimal::new(110, 0)));
+        assert_eq!(intent.stop_loss, Some(Decimal::new(95, 0)));
+    }
+
+    #[test]
+    fn can_deserialize_numeric_and_string_amounts() {
+        let amount: Amount = serde_json::from_str(r#"{"shares": 100}"#).unwrap();
+        assert_eq!(amount, Amount::Shares(Decimal::new(100, 0)));
+        let amount: Amount = serde_json::from_str(r#"{"shares": "100"}"#).unwrap();
+        assert_eq!(amount, Amount::Shares(Decimal::new(100, 0)));
+        let amount: Amount = serde_json::from_str(r#"{"dollars": 100.25}"#).unwrap();
+        assert_eq!(amount, Amount::Dollars(Decimal::new(10025, 2)));
+        let amount: Amount = serde_json::from_str(r#"{"dollars": "100.25"}"#).unwrap();
+        assert_eq!(amount, Amount::Dollars(Decimal::new(10025, 2)));
+        let amount: FlatAmount = serde_json::from_str(r#"{"type": "shares", "value": 5}"#).unwrap();
+        assert_eq!(amount, FlatAmount::Shares(Decimal::new(5, 0)));
+    }
+
+    #[test]
+    fn validate_catches_invalid_deserialized_intent() {
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert!(intent.validate().is_ok());
+
+        let mut value = serde_json::to_value(&intent).unwrap();
+        value["identifier"] = serde_json::json!("all");
+        value["amount"] = serde_json::json!({"dollars": "100"});
+        let deserialized: PositionIntent = serde_json::from_value(value).unwrap();
+        assert!(matches!(
+            deserialized.validate(),
+            Err(Error::InvalidCombination)
+        ));
+    }
+
+    #[test]
+    fn can_create_closing_intent() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .sub_strategy("B")
+            .shares(-10)
+            .limit_price(100)
+            .update_policy(UpdatePolicy::RetainShort)
+            .build()
+            .unwrap();
+        let closing = intent.closing_intent().unwrap();
+        assert_ne!(closing.id, intent.id);
+        assert_eq!(closing.strategy, "A");
+        assert_eq!(closing.sub_strategy, Some("B".into()));
+        assert_eq!(closing.identifier, intent.identifier);
+        assert_eq!(closing.amount, Amount::Zero);
+        assert_eq!(closing.update_policy, UpdatePolicy::Update);
+        assert_eq!(closing.limit_price, None);
+    }
+
+    #[test]
+    fn priority_defaults_to_normal() {
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert_eq!(intent.priority, Priority::Normal);
+        let mut value = serde_json::to_value(&intent).unwrap();
+        value.as_object_mut().unwrap().remove("priority");
+        let deserialized: PositionIntent = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.priority, Priority::Normal);
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .priority(Priority::High)
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert_eq!(serialized["priority"], "high");
+    }
+
+    #[test]
+    fn intents_are_scheduled_by_priority_then_timestamp() {
+        let now = Utc::now();
+        let intent = |ticker: &str, priority: Priority, age: i64| {
+            let mut intent = PositionIntent::for_ticker("A", ticker)
+                .priority(priority)
+                .build()
+                .unwrap();
+            intent.timestamp = now - Duration::seconds(age);
+            intent
+        };
+        let mut intents = vec![
+            intent("NEW_NORMAL", Priority::Normal, 0),
+            intent("LOW", Priority::Low, 10),
+            intent("OLD_NORMAL", Priority::Normal, 5),
+            intent("HIGH", Priority::High, 0),
+        ];
+        intents.sort_by(PositionIntent::scheduling_cmp);
+        let order: Vec<_> = intents.into_iter().map(|i| i.identifier).collect();
+        assert_eq!(
+            order,
+            vec![
+                Identifier::from("HIGH"),
+                Identifier::from("OLD_NORMAL"),
+                Identifier::from("NEW_NORMAL"),
+                Identifier::from("LOW"),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_amount_from_value() {
+        use serde_json::json;
+
+        let values = vec![
+            (
+                json!({"dollars": "100"}),
+                Amount::Dollars(Decimal::new(100, 0)),
+            ),
+            (json!({"shares": 10}), Amount::Shares(Decimal::new(10, 0))),
+            (json!("zero"), Amount::Zero),
+            (
+                json!({"type": "dollars", "value": "2.5"}),
+                Amount::Dollars(Decimal::new(25, 1)),
+            ),
+            (json!({"type": "zero"}), Amount::Zero),
+        ];
+        for (value, expected) in values {
+            assert_eq!(Amount::from_value(&value).unwrap(), expected);
+        }
+        assert!(matches!(
+            Amount::from_value(&json!({"euros": "100"})),
+            Err(Error::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn can_convert_with_rounding() {
+        let dollars = Amount::Dollars(Decimal::new(1000, 0));
+        let price = Decimal::new(16, 0);
+        // 1000 / 16 = 62.5
+        assert_eq!(
+            dollars.convert_to_shares(price, 0).unwrap(),
+            Amount::Shares(Decimal::new(63, 0))
+        );
+        assert_eq!(
+            dollars
+                .convert_to_shares_with_rounding(price, 0, RoundingStrategy::ToZero)
+                .unwrap(),
+            Amount::Shares(Decimal::new(62, 0))
+        );
+        assert_eq!(
+            dollars
+                .convert_to_shares_with_rounding(price, 0, RoundingStrategy::MidpointAwayFromZero)
+                .unwrap(),
+            Amount::Shares(Decimal::new(63, 0))
+        );
+        assert_eq!(
+            Amount::Shares(Decimal::new(3, 0))
+                .convert_to_dollars(Decimal::new(33335, 3), 2)
+                .unwrap(),
+            Amount::Dollars(Decimal::new(10001, 2))
+        );
+        assert!(matches!(
+            dollars.convert_to_shares(Decimal::ZERO, 0),
+            Err(Error::InvalidPrice(_))
+        ));
+    }
+
+    #[test]
+    fn can_diff_intents() {
+        let prior = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(100)
+            .limit_price(10)
+            .build()
+            .unwrap();
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(200)
+            .limit_price(11)
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.diff(&prior),
+            vec![
+                FieldChange {
+                    field: "amount",
+                    old: "$100".into(),
+                    new: "$200".into()
+                },
+                FieldChange {
+                    field: "limit_price",
+                    old: "10".into(),
+                    new: "11".into()
+                },
+            ]
+        );
+        assert!(intent.diff(&intent).is_empty());
+
+        let rescaled = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(Decimal::new(2000, 1))
+            .limit_price(Decimal::new(1100, 2))
+            .build()
+            .unwrap();
+        assert!(rescaled.diff(&intent).is_empty());
+    }
+
+    #[test]
+    fn can_copy_and_modify_via_builder() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .sub_strategy("B")
+            .shares(10)
+            .limit_price(100)
+            .priority(Priority::High)
+            .build()
+            .unwrap();
+        let modified = intent.to_builder().shares(20).build().unwrap();
+        assert_ne!(modified.id, intent.id);
+        assert_eq!(modified.amount, Amount::Shares(Decimal::new(20, 0)));
+        assert_eq!(
+            PositionIntent {
+                id: intent.id,
+                timestamp: intent.timestamp,
+                amount: intent.amount.clone(),
+                ..modified
+            },
+            intent
+        );
+    }
+
+    #[test]
+    fn can_scale_amount() {
+        let amount = Amount::Dollars(Decimal::new(100, 0));
+        assert_eq!(
+            amount.scale_by(Decimal::new(15, 1)),
+            Amount::Dollars(Decimal::new(150, 0))
+        );
+        assert_eq!(
+            amount.checked_scale_by(Decimal::new(-2, 0)).unwrap(),
+            Amount::Dollars(Decimal::new(-200, 0))
+        );
+        assert_eq!(Amount::Zero.scale_by(Decimal::MAX), Amount::Zero);
+        assert!(matches!(
+            amount.checked_scale_by(Decimal::MAX),
+            Err(Error::AmountOverflow(_, _))
+        ));
+    }
+
+    #[test]
+    fn can_size_from_risk() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .size_from_risk(500, Decimal::new(25, 1))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(intent.amount, Amount::Shares(Decimal::new(200, 0)));
+        assert!(matches!(
+            PositionIntent::for_ticker("A", "AAPL").size_from_risk(500, 0),
+            Err(Error::InvalidStopDistance(_))
+        ));
+        assert!(matches!(
+            PositionIntent::for_ticker("A", "AAPL").size_from_risk(500, -5),
+            Err(Error::InvalidStopDistance(_))
+        ));
+        assert!(matches!(
+            PositionIntent::for_ticker("A", "AAPL")
+                .size_from_risk(Decimal::MAX, Decimal::new(1, 28)),
+            Err(Error::AmountOverflow(_, _))
+        ));
+    }
+
+    #[test]
+    fn can_expand_all_intent() {
+        let tickers = vec!["AAPL".to_string(), "MSFT".to_string(), "TSLA".to_string()];
+        let intent = PositionIntent::builder("A", Identifier::All, Amount::Zero)
+            .build()
+            .unwrap();
+        let expanded = intent.expand(&tickers);
+        assert_eq!(expanded.len(), 3);
+        for (child, ticker) in expanded.iter().zip(&tickers) {
+            assert_ne!(child.id, intent.id);
+            assert_eq!(child.identifier, Identifier::Ticker(ticker.clone()));
+            assert_eq!(child.amount, Amount::Zero);
+            assert_eq!(child.strategy, "A");
+        }
+
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert_eq!(intent.expand(&tickers), vec![intent]);
+    }
+
+    #[test]
+    fn conversions_preserve_sign() {
+        let price = Decimal::new(50, 0);
+        assert_eq!(
+            Amount::Dollars(Decimal::new(-1000, 0))
+                .convert_to_shares(price, 2)
+                .unwrap(),
+            Amount::Shares(Decimal::new(-20, 0))
+        );
+        assert_eq!(
+            Amount::Shares(Decimal::new(-20, 0))
+                .convert_to_dollars(price, 2)
+                .unwrap(),
+            Amount::Dollars(Decimal::new(-1000, 0))
+        );
+        assert!(matches!(
+            Amount::Dollars(Decimal::new(1000, 0)).convert_to_shares(-price, 2),
+            Err(Error::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            Amount::Shares(Decimal::new(20, 0)).convert_to_dollars(-price, 2),
+            Err(Error::InvalidPrice(_))
+        ));
+    }
+
+    #[test]
+    fn serialized_len_matches_json_length() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .sub_strategy("B")
+            .dollars(Decimal::new(10025, 2))
+            .limit_price(100)
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.serialized_len(),
+            serde_json::to_string(&intent).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn can_tag_intents() {
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert!(serialized.get("tags").is_none());
+        let deserialized: PositionIntent = serde_json::from_value(serialized).unwrap();
+        assert!(deserialized.tags.is_empty());
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .tag("source", "backtest")
+            .tag("regime", "trending")
+            .build()
+            .unwrap();
+        assert_eq!(intent.tags.len(), 2);
+        let serialized = serde_json::to_string(&intent).unwrap();
+        assert!(serialized.contains(r#""tags":{"regime":"trending","source":"backtest"}"#));
+        let deserialized: PositionIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(intent, deserialized);
+    }
+
+    #[test]
+    fn can_check_readiness() {
+        let now = Utc::now();
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert!(intent.is_ready(now));
+        assert!(!intent.is_expired(now));
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .after(now)
+            .before(now + Duration::hours(1))
+            .build()
+            .unwrap();
+        assert!(!intent.is_ready(now - Duration::seconds(1)));
+        assert!(intent.is_ready(now));
+        assert!(intent.is_ready(now + Duration::seconds(1)));
+        assert!(!intent.is_expired(now + Duration::hours(1)));
+        assert!(intent.is_expired(now + Duration::hours(2)));
+    }
+
+    #[test]
+    fn zero_amount_is_omitted() {
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert!(serialized.get("amount").is_none());
+        let deserialized: PositionIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.amount, Amount::Zero);
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(0)
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert_eq!(serialized["amount"], serde_json::json!({"shares": "0"}));
+    }
+
+    #[test]
+    fn semantically_equal_intents_share_dedup_key() {
+        let a = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(100)
+            .build()
+            .unwrap();
+        let b = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(Decimal::new(1000, 1))
+            .limit_price(10)
+            .build()
+            .unwrap();
+        assert_ne!(a.id, b.id);
+        assert_eq!(a.dedup_key(), b.dedup_key());
+
+        let c = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(200)
+            .build()
+            .unwrap();
+        let d = PositionIntent::for_ticker("A", "AAPL")
+            .sub_strategy("B")
+            .dollars(100)
+            .build()
+            .unwrap();
+        let e = PositionIntent::for_ticker("A", "AAPL")
+            .shares(100)
+            .build()
+            .unwrap();
+        assert_ne!(a.dedup_key(), c.dedup_key());
+        assert_ne!(a.dedup_key(), d.dedup_key());
+        assert_ne!(a.dedup_key(), e.dedup_key());
+    }
+
+    #[test]
+    fn can_require_whole_shares() {
+        assert!(Amount::Shares(Decimal::new(100, 0))
+            .require_whole_shares()
+            .is_ok());
+        assert!(Amount::Shares(Decimal::new(-1000, 1))
+            .require_whole_shares()
+            .is_ok());
+        assert!(matches!(
+            Amount::Shares(Decimal::new(1005, 1)).require_whole_shares(),
+            Err(Error::FractionalShares(_))
+        ));
+        assert!(Amount::Dollars(Decimal::new(1005, 1))
+            .require_whole_shares()
+            .is_ok());
+        assert!(Amount::Zero.require_whole_shares().is_ok());
+    }
+
+    #[test]
+    fn can_display_intent() {
+        let intent = PositionIntent::for_ticker("momentum", "AAPL")
+            .dollars(1000)
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.to_string(),
+            "AAPL target $1000 (strategy=momentum, policy=update)"
+        );
+        let intent = PositionIntent::for_ticker("momentum", "AAPL")
+            .sub_strategy("fast")
+            .shares(-10)
+            .update_policy(UpdatePolicy::RetainShort)
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.to_string(),
+            "AAPL target -10 sh (strategy=momentum:fast, policy=retain_short)"
+        );
+        let intent = PositionIntent::builder("momentum", Identifier::All, Amount::Zero)
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.to_string(),
+            "ALL flatten (strategy=momentum, policy=update)"
+        );
+    }
+
+    #[test]
+    fn can_resolve_update_policies() {
+        use UpdatePolicy::*;
+        let cases = vec![
+            (Update, Update, Update),
+            (Update, Retain, Update),
+            (Update, RetainLong, Update),
+            (Update, RetainShort, Update),
+            (Retain, Retain, Retain),
+            (Retain, RetainLong, RetainLong),
+            (Retain, RetainShort, RetainShort),
+            (RetainLong, RetainLong, RetainLong),
+            (RetainShort, RetainShort, RetainShort),
+            (RetainLong, RetainShort, Update),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(a.clone().resolve(b.clone()), expected);
+            assert_eq!(b.resolve(a), expected);
+        }
+    }
+
+    #[test]
+    fn can_derive_order_type() {
+        let builder = PositionIntent::for_ticker("A", "AAPL").shares(10);
+        let order_type =
+            |builder: PositionIntentBuilder| builder.build().unwrap().derive_order_type();
+        assert_eq!(order_type(builder.clone()), OrderType::Market);
+        assert_eq!(
+            order_type(builder.clone().limit_price(101)),
+            OrderType::Limit {
+                limit_price: Decimal::new(101, 0)
+            }
+        );
+        assert_eq!(
+            order_type(builder.clone().stop_price(100)),
+            OrderType::Stop {
+                stop_price: Decimal::new(100, 0)
+            }
+        );
+        assert_eq!(
+            order_type(builder.stop_price(100).limit_price(101)),
+            OrderType::StopLimit {
+                stop_price: Decimal::new(100, 0),
+                limit_price: Decimal::new(101, 0)
+            }
+        );
+    }
+
+    #[test]
+    fn can_compare_amounts() {
+        let dollars = |x| Amount::Dollars(Decimal::new(x, 0));
+        let shares = |x| Amount::Shares(Decimal::new(x, 0));
+        assert_eq!(dollars(1).compare(&dollars(2)), Some(Ordering::Less));
+        assert_eq!(shares(-5).compare(&shares(3)), Some(Ordering::Less));
+        assert_eq!(Amount::Zero.compare(&dollars(1)), Some(Ordering::Less));
+        assert_eq!(Amount::Zero.compare(&shares(-1)), Some(Ordering::Greater));
+        assert_eq!(Amount::Zero.compare(&dollars(0)), Some(Ordering::Equal));
+        assert_eq!(Amount::Zero.compare(&Amount::Zero), Some(Ordering::Equal));
+        assert_eq!(dollars(1).compare(&shares(1)), None);
+    }
+
+    #[test]
+    fn can_format_amount_for_broker() {
+        assert_eq!(
+            Amount::Dollars(Decimal::new(100, 0)).to_broker_string(2),
+            "100.00"
+        );
+        assert_eq!(
+            Amount::Dollars(Decimal::new(100125, 3)).to_broker_string(2),
+            "100.13"
+        );
+        assert_eq!(
+            Amount::Dollars(Decimal::new(100124, 3)).to_broker_string(2),
+            "100.12"
+        );
+        assert_eq!(
+            Amount::Dollars(Decimal::new(-100125, 3)).to_broker_string(2),
+            "-100.13"
+        );
+        assert_eq!(
+            Amount::Shares(Decimal::new(105, 1)).to_broker_string(0),
+            "11"
+        );
+        assert_eq!(
+            Amount::Shares(Decimal::new(104, 1)).to_broker_string(0),
+            "10"
+        );
+        assert_eq!(Amount::Zero.to_broker_string(2), "0");
+    }
+
+    #[test]
+    fn strategy_names_must_be_well_formed() {
+        let intent = |strategy: &str, sub_strategy: Option<&str>| {
+            let builder = PositionIntent::builder(strategy, "AAPL", Amount::Zero);
+            match sub_strategy {
+                Some(sub_strategy) => builder.sub_strategy(sub_strategy).build(),
+                None => builder.build(),
+            }
+        };
+        assert!(intent("momentum", None).is_ok());
+        assert!(intent("momentum", Some("fast")).is_ok());
+        for (strategy, sub_strategy) in [
+            ("", None),
+            ("momentum:fast", None),
+            ("momentum", Some("")),
+            ("momentum", Some("fast:1")),
+        ] {
+            assert!(matches!(
+                intent(strategy, sub_strategy),
+                Err(Error::InvalidStrategyName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn can_express_intent_in_shares() {
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1000, 0)))
+            .decision_price(Decimal::new(160, 0))
+            .build()
+            .unwrap()
+            .in_shares()
+            .unwrap();
+        assert_eq!(intent.amount, Amount::Shares(Decimal::new(625, 2)));
+
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1000, 0)))
+            .decision_price(Decimal::new(150, 0))
+            .build()
+            .unwrap()
+            .in_shares()
+            .unwrap();
+        assert_eq!(
+            intent.amount,
+            Amount::Shares(Decimal::new(1000, 0) / Decimal::new(150, 0))
+        );
+
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Shares(Decimal::new(10, 0)))
+            .build()
+            .unwrap()
+            .in_shares()
+            .unwrap();
+        assert_eq!(intent.amount, Amount::Shares(Decimal::new(10, 0)));
+
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1000, 0)))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            intent.in_shares(),
+            Err(Error::MissingDecisionPrice)
+        ));
+    }
+
+    #[test]
+    fn leverage_scales_dollar_amounts() {
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1000, 0)))
+            .leverage(Decimal::new(25, 1))
+            .build()
+            .unwrap();
+        assert_eq!(intent.amount, Amount::Dollars(Decimal::new(2500, 0)));
+
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Shares(Decimal::new(10, 0)))
+            .leverage(Decimal::new(25, 1))
+            .build()
+            .unwrap();
+        assert_eq!(intent.amount, Amount::Shares(Decimal::new(10, 0)));
+    }
+
+    #[test]
+    fn can_tell_long_from_short() {
+        assert!(Amount::Dollars(Decimal::new(100, 0)).is_long());
+        assert!(!Amount::Dollars(Decimal::new(100, 0)).is_short());
+        assert!(Amount::Shares(Decimal::new(-10, 0)).is_short());
+        assert!(!Amount::Shares(Decimal::new(-10, 0)).is_long());
+        for amount in [
+            Amount::Zero,
+            Amount::Shares(Decimal::ZERO),
+            Amount::Dollars(-Decimal::ZERO),
+        ] {
+            assert!(!amount.is_long());
+            assert!(!amount.is_short());
+        }
+    }
+
+    #[test]
+    fn risk_view_serializes_subset_of_fields() {
+        let intent = PositionIntent::builder("A", "AAPL", Amount::Dollars(Decimal::new(1000, 0)))
+            .sub_strategy("fast")
+            .decision_price(Decimal::new(150, 0))
+            .limit_price(Decimal::new(151, 0))
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(RiskView(&intent)).unwrap();
+        let mut keys: Vec<_> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "amount",
+                "id",
+                "identifier",
+                "strategy",
+                "timestamp",
+                "update_policy"
+            ]
+        );
+        assert_eq!(value["amount"], serde_json::json!({"dollars": "1000"}));
+    }
+
+    #[test]
+    fn can_populate_idempotency_key() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .if_not_exists()
+            .build()
+            .unwrap();
+        let key = format!("{:016x}", intent.dedup_key());
+        assert_eq!(intent.idempotency_key.as_deref(), Some(key.as_str()));
+        let serialized = serde_json::to_string(&intent).unwrap();
+        assert!(serialized.contains(&format!(r#""idempotency_key":"{}""#, key)));
+        assert_eq!(
+            serde_json::from_str::<PositionIntent>(&serialized).unwrap(),
+            intent
+        );
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .idempotency_key("order-1")
+            .if_not_exists()
+            .build()
+            .unwrap();
+        assert_eq!(intent.idempotency_key.as_deref(), Some("order-1"));
+
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert!(intent.idempotency_key.is_none());
+        assert!(!serde_json::to_string(&intent)
+            .unwrap()
+            .contains("idempotency_key"));
+    }
+
+    #[test]
+    fn can_aggregate_mixed_amounts() {
+        let amounts = [
+            Amount::Dollars(Decimal::new(1000, 0)),
+            Amount::Shares(Decimal::new(10, 0)),
+            Amount::Zero,
+            Amount::Dollars(Decimal::new(-250, 0)),
+            Amount::Shares(Decimal::new(5, 1)),
+        ];
+        assert_eq!(
+            aggregate(&amounts).unwrap(),
+            (Decimal::new(750, 0), Decimal::new(105, 1))
+        );
+        assert_eq!(aggregate(&[]).unwrap(), (Decimal::ZERO, Decimal::ZERO));
+        assert!(matches!(
+            aggregate(&[Amount::Dollars(Decimal::MAX), Amount::Dollars(Decimal::MAX)]),
+            Err(Error::AmountOverflow(_, _))
+        ));
+    }
+
+    #[test]
+    fn can_compute_active_duration() {
+        let after = Utc::now();
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .after(after)
+            .build()
+            .unwrap();
+        assert_eq!(intent.active_duration(after - Duration::minutes(1)), None);
+        assert_eq!(intent.active_duration(after), Some(Duration::zero()));
+        assert_eq!(
+            intent.active_duration(after + Duration::minutes(5)),
+            Some(Duration::minutes(5))
+        );
+
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert_eq!(intent.active_duration(after), None);
+    }
+
+    #[test]
+    fn limit_must_be_within_opt_in_band() {
+        let builder = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .decision_price(100);
+        assert!(builder.clone().limit_price(110).build().is_ok());
+        assert!(builder
+            .clone()
+            .limit_band_bps(100)
+            .limit_price(Decimal::new(1009, 1))
+            .build()
+            .is_ok());
+        assert!(builder
+            .clone()
+            .limit_band_bps(100)
+            .limit_price(99)
+            .build()
+            .is_ok());
+        assert!(matches!(
+            builder.limit_band_bps(100).limit_price(110).build(),
+            Err(Error::LimitOutsideBand(_, _, 100))
+        ));
+
+        let errors = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .decision_price(Decimal::MAX)
+            .limit_price(Decimal::MAX)
+            .limit_band_bps(20_000)
+            .build_all_errors()
+            .unwrap_err();
+        assert!(errors
+            .iter()
+            .any(|error| matches!(error, Error::LimitOutsideBand(_, _, 20_000))));
+    }
+
+    #[test]
+    fn can_convert_to_primitives() {
+        assert_eq!(Amount::Dollars(Decimal::new(-10025, 2)).to_f64(), -100.25);
+        assert_eq!(Amount::Shares(Decimal::new(10, 0)).to_f64(), 10.0);
+        assert_eq!(Amount::Zero.to_f64(), 0.0);
+
+        assert_eq!(
+            Amount::Shares(Decimal::new(109, 1)).to_i64_shares(),
+            Some(10)
+        );
+        assert_eq!(
+            Amount::Shares(Decimal::new(-109, 1)).to_i64_shares(),
+            Some(-10)
+        );
+        assert_eq!(Amount::Shares(Decimal::MAX).to_i64_shares(), Some(i64::MAX));
+        assert_eq!(Amount::Shares(Decimal::MIN).to_i64_shares(), Some(i64::MIN));
+        assert_eq!(Amount::Zero.to_i64_shares(), Some(0));
+        assert_eq!(Amount::Dollars(Decimal::new(10, 0)).to_i64_shares(), None);
+    }
+
+    #[test]
+    fn can_use_asset_class_qualified_identifiers() {
+        let equity = Identifier::asset("AAPL", AssetClass::Equity);
+        let crypto = Identifier::asset("BTC/USD", AssetClass::Crypto);
+        assert_eq!(crypto.symbol(), Some("BTC/USD"));
+        assert_eq!(crypto.asset_class(), Some(AssetClass::Crypto));
+        assert_eq!(Identifier::from("AAPL").asset_class(), None);
+        assert_eq!(
+            serde_json::to_string(&crypto).unwrap(),
+            r#"{"asset":{"symbol":"BTC/USD","class":"crypto"}}"#
+        );
+
+        for identifier in [equity, crypto] {
+            let intent = PositionIntent::builder("A", identifier.clone(), Amount::Zero)
+                .build()
+                .unwrap();
+            let serialized = serde_json::to_string(&intent).unwrap();
+            let deserialized: PositionIntent = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.identifier, identifier);
+            assert_ne!(
+                intent.dedup_key(),
+                PositionIntent::builder("A", identifier.symbol().unwrap(), Amount::Zero)
+                    .build()
+                    .unwrap()
+                    .dedup_key()
+            );
+        }
+    }
+
+    #[test]
+    fn can_split_amount() {
+        assert_eq!(
+            Amount::Shares(Decimal::new(10, 0)).split(3),
+            vec![
+                Amount::Shares(Decimal::new(3, 0)),
+                Amount::Shares(Decimal::new(3, 0)),
+                Amount::Shares(Decimal::new(4, 0)),
+            ]
+        );
+        assert_eq!(
+            Amount::Dollars(Decimal::new(-10000, 2)).split(3),
+            vec![
+                Amount::Dollars(Decimal::new(-3333, 2)),
+                Amount::Dollars(Decimal::new(-3333, 2)),
+                Amount::Dollars(Decimal::new(-3334, 2)),
+            ]
+        );
+        assert_eq!(Amount::Zero.split(2), vec![Amount::Zero, Amount::Zero]);
+        assert!(Amount::Shares(Decimal::ONE).split(0).is_empty());
+    }
+
+    #[test]
+    fn can_scale_in_with_ladder() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(100)
+            .limit_price(150)
+            .build()
+            .unwrap();
+        let ladder = intent.scale_in(3, Decimal::new(-50, 2)).unwrap();
+        assert_eq!(ladder.len(), 3);
+        let amounts: Vec<_> = ladder.iter().map(|child| child.amount.clone()).collect();
+        assert_eq!(
+            aggregate(&amounts).unwrap(),
+            (Decimal::ZERO, Decimal::new(100, 0))
+        );
+        let prices: Vec<_> = ladder
+            .iter()
+            .map(|child| child.limit_price.unwrap())
+            .collect();
+        assert_eq!(
+            prices,
+            [
+                Decimal::new(150, 0),
+                Decimal::new(1495, 1),
+                Decimal::new(149, 0)
+            ]
+        );
+        assert_ne!(ladder[0].id, ladder[1].id);
+        assert_ne!(ladder[0].id, intent.id);
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(100)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            intent.scale_in(3, Decimal::ONE),
+            Err(Error::MissingDecisionPrice)
+        ));
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(100)
+            .limit_price(150)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            intent.scale_in(3, Decimal::MAX),
+            Err(Error::InvalidPrice(_))
+        ));
+    }
+
+    #[test]
+    fn can_round_trip_compact_array() {
+        let intents = [
+            PositionIntent::builder("momentum", "AAPL", Amount::Shares(Decimal::new(100, 0)))
+                .sub_strategy("fast")
+                .limit_price(150)
+                .after(Utc::now())
+                .tag("source", "backtest")
+                .build()
+                .unwrap(),
+            PositionIntent::builder("momentum", Identifier::All, Amount::Zero)
+                .build()
+                .unwrap(),
+            PositionIntent::builder(
+                "momentum",
+                Identifier::asset("BTC/USD", AssetClass::Crypto),
+                Amount::Dollars(Decimal::new(-5000, 0)),
+            )
+            .build()
+            .unwrap(),
+            PositionIntent::builder("momentum", "all", Amount::Zero)
+                .build()
+                .unwrap(),
+            PositionIntent::builder("momentum", "*", Amount::Zero)
+                .build()
+                .unwrap(),
+        ];
+        for intent in intents {
+            let compact = intent.to_compact_array();
+            assert_eq!(compact.as_array().unwrap().len(), COMPACT_FIELDS.len());
+            assert_eq!(
+                PositionIntent::from_compact_array(&compact).unwrap(),
+                intent
+            );
+        }
+
+        let intent =
+            PositionIntent::builder("momentum", "AAPL", Amount::Shares(Decimal::new(100, 0)))
+                .build()
+                .unwrap();
+        let compact = intent.to_compact_array();
+        assert_eq!(compact[2], "momentum");
+        assert_eq!(compact[5], "AAPL");
+        assert_eq!(compact[6], "shares");
+        assert_eq!(compact[7], "100");
+        let wildcard = PositionIntent::builder("momentum", "all", Amount::Zero)
+            .build()
+            .unwrap()
+            .to_compact_array();
+        assert_eq!(wildcard[5], serde_json::json!({"ticker": "all"}));
+        let asset = PositionIntent::builder(
+            "momentum",
+            Identifier::asset("BTC/USD", AssetClass::Crypto),
+            Amount::Zero,
+        )
+        .build()
+        .unwrap()
+        .to_compact_array();
+        assert_eq!(
+            asset[5],
+            serde_json::to_value(Identifier::asset("BTC/USD", AssetClass::Crypto)).unwrap()
+        );
+
+        let truncated = Value::Array(compact.as_array().unwrap()[..8].to_vec());
+        assert_eq!(
+            PositionIntent::from_compact_array(&truncated).unwrap(),
+            intent
+        );
+        assert!(matches!(
+            PositionIntent::from_compact_array(&serde_json::json!({"strategy": "momentum"})),
+            Err(Error::InvalidCompactArray(_))
+        ));
+    }
+
+    #[test]
+    fn all_intents_must_update_or_retain() {
+        for policy in [UpdatePolicy::Update, UpdatePolicy::Retain] {
+            assert!(PositionIntent::builder("A", Identifier::All, Amount::Zero)
+                .update_policy(policy)
+                .build()
+                .is_ok());
+        }
+        for policy in [UpdatePolicy::RetainLong, UpdatePolicy::RetainShort] {
+            assert!(matches!(
+                PositionIntent::builder("A", Identifier::All, Amount::Zero)
+                    .update_policy(policy)
+                    .build(),
+                Err(Error::InvalidAllPolicy(_))
+            ));
+        }
+        assert!(PositionIntent::builder("A", "AAPL", Amount::Zero)
+            .update_policy(UpdatePolicy::RetainLong)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn can_keep_latest_intent_per_identifier() {
+        let now = Utc::now();
+        let intent = |strategy: &str, ticker: &str, shares: i64, age: i64| {
+            let mut intent = PositionIntent::for_ticker(strategy, ticker)
+                .shares(shares)
+                .build()
+                .unwrap();
+            intent.timestamp = now - Duration::seconds(age);
+            intent
+        };
+        let intents = vec![
+            intent("A", "AAPL", 1, 30),
+            intent("A", "MSFT", 2, 20),
+            intent("A", "AAPL", 3, 10),
+            intent("B", "AAPL", 4, 40),
+            intent("A", "MSFT", 5, 50),
+        ];
+        let latest: Vec<_> = latest_per_identifier(intents)
+            .into_iter()
+            .map(|intent| intent.amount)
+            .collect();
+        assert_eq!(
+            latest,
+            [
+                Amount::Shares(Decimal::new(2, 0)),
+                Amount::Shares(Decimal::new(3, 0)),
+                Amount::Shares(Decimal::new(4, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_set_limit_from_decision_price() {
+        let builder = PositionIntent::for_ticker("A", "AAPL")
+            .decision_price(100)
+            .limit_from_decision(50);
+        let buy = builder.clone().shares(10).build().unwrap();
+        assert_eq!(buy.limit_price, Some(Decimal::new(1005, 1)));
+        let sell = builder.shares(-10).build().unwrap();
+        assert_eq!(sell.limit_price, Some(Decimal::new(995, 1)));
+
+        assert!(matches!(
+            PositionIntent::for_ticker("A", "AAPL")
+                .shares(10)
+                .limit_from_decision(50)
+                .build(),
+            Err(Error::MissingDecisionPrice)
+        ));
+        assert!(matches!(
+            PositionIntent::for_ticker("A", "AAPL")
+                .shares(10)
+                .decision_price(Decimal::MAX)
+                .limit_from_decision(50)
+                .build(),
+            Err(Error::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            PositionIntent::for_ticker("A", "AAPL")
+                .shares(10)
+                .decision_price(100)
+                .limit_from_decision(-20_000)
+                .build(),
+            Err(Error::InvalidPrice(price)) if price == Decimal::new(-100, 0)
+        ));
+    }
+
+    #[test]
+    fn can_round_trip_scaled_integers() {
+        let dollars = Amount::Dollars(Decimal::new(-12345, 2));
+        assert_eq!(dollars.to_scaled_i128(2), Some(-12345));
+        assert_eq!(
+            Amount::from_scaled_i128(-12345, 2, Amount::Dollars),
+            Some(dollars.clone())
+        );
+        assert_eq!(dollars.to_scaled_i128(4), Some(-1234500));
+        assert_eq!(dollars.to_scaled_i128(1), None);
+
+        let shares = Amount::Shares(Decimal::new(15, 1));
+        assert_eq!(shares.to_scaled_i128(9), Some(1_500_000_000));
+        assert_eq!(
+            Amount::from_scaled_i128(1_500_000_000, 9, Amount::Shares),
+            Some(shares)
+        );
+
+        assert_eq!(
+            Amount::Shares(Decimal::new(100, 0)).to_scaled_i128(0),
+            Some(100)
+        );
+        assert_eq!(Amount::Zero.to_scaled_i128(2), Some(0));
+        assert_eq!(Amount::Shares(Decimal::MAX).to_scaled_i128(20), None);
+    }
+
+    #[test]
+    fn can_serialize_signal() {
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .signal(Decimal::new(75, 2))
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_string(&intent).unwrap();
+        assert!(serialized.contains(r#""signal":"0.75""#));
+        let deserialized: PositionIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.signal, Some(Decimal::new(75, 2)));
+
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        let serialized = serde_json::to_string(&intent).unwrap();
+        assert!(!serialized.contains("signal"));
+        let deserialized: PositionIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.signal, None);
+    }
+
+    #[test]
+    fn strategy_dollars_must_be_within_cap() {
+        let intents = [
+            PositionIntent::for_ticker("A", "AAPL")
+                .dollars(600)
+                .build()
+                .unwrap(),
+            PositionIntent::for_ticker("A", "MSFT")
+                .dollars(-300)
+                .build()
+                .unwrap(),
+            PositionIntent::for_ticker("A", "TSLA")
+                .shares(1)
+                .decision_price(100)
+                .build()
+                .unwrap(),
+            PositionIntent::for_ticker("A", "GOOG")
+                .shares(1000)
+                .build()
+                .unwrap(),
+            PositionIntent::for_ticker("B", "AAPL")
+                .dollars(5000)
+                .build()
+                .unwrap(),
+        ];
+        let mut caps = HashMap::new();
+        caps.insert("A".to_string(), Decimal::new(1000, 0));
+        assert!(validate_strategy_cap(&intents, &caps).is_ok());
+
+        caps.insert("A".to_string(), Decimal::new(999, 0));
+        assert!(matches!(
+            validate_strategy_cap(&intents, &caps),
+            Err(Error::StrategyCapExceeded(strategy, _, _)) if strategy == "A"
+        ));
+
+        let overflowing = [PositionIntent::for_ticker("A", "AAPL")
+            .shares(Decimal::MAX)
+            .decision_price(10)
+            .build()
+            .unwrap()];
+        assert!(matches!(
+            validate_strategy_cap(&overflowing, &caps),
+            Err(Error::AmountOverflow(_, _))
+        ));
+        let overflowing = vec![
+            PositionIntent::for_ticker("A", "AAPL")
+                .dollars(Decimal::MAX)
+                .build()
+                .unwrap();
+            2
+        ];
+        assert!(matches!(
+            validate_strategy_cap(&overflowing, &caps),
+            Err(Error::StrategyCapExceeded(_, total, _)) if total == Decimal::MAX
+        ));
+    }
+
+    #[test]
+    fn can_compute_implied_side() {
+        let current = Decimal::new(10, 0);
+        let intent = |amount| {
+            PositionIntent::builder("A", "AAPL", amount)
+                .decision_price(100)
+                .build()
+                .unwrap()
+        };
+        let side = |amount, current| intent(amount).implied_side(current).unwrap();
+        assert_eq!(
+            side(Amount::Shares(Decimal::new(20, 0)), current),
+            Some(Side::Buy)
+        );
+        assert_eq!(
+            side(Amount::Dollars(Decimal::new(500, 0)), current),
+            Some(Side::Sell)
+        );
+        assert_eq!(side(Amount::Zero, current), Some(Side::Sell));
+        assert_eq!(side(Amount::Dollars(Decimal::new(1000, 0)), current), None);
+        assert_eq!(side(Amount::Zero, Decimal::ZERO), None);
+
+        let intent = PositionIntent::for_ticker("A", "AAPL")
+            .dollars(1000)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            intent.implied_side(current),
+            Err(Error::MissingDecisionPrice)
+        ));
+    }
+
+    #[test]
+    fn can_normalize_zero() {
+        assert_eq!(
+            Amount::Dollars(Decimal::ZERO).normalize_zero(),
+            Amount::Zero
+        );
+        assert_eq!(
+            Amount::Shares(Decimal::new(0, 2)).normalize_zero(),
+            Amount::Zero
+        );
+        assert_eq!(
+            Amount::Dollars(-Decimal::ZERO).normalize_zero(),
+            Amount::Zero
+        );
+        assert_eq!(Amount::Zero.normalize_zero(), Amount::Zero);
+        assert_eq!(
+            Amount::Shares(Decimal::ONE).normalize_zero(),
+            Amount::Shares(Decimal::ONE)
+        );
+        assert!(Amount::Dollars(Decimal::ZERO)
+            .normalize_zero()
+            .merge(Amount::Shares(Decimal::ONE))
+            .is_ok());
+    }
+
+    #[test]
+    fn can_apply_patch() {
+        let mut intent = PositionIntent::for_ticker("A", "AAPL")
+            .shares(10)
+            .limit_price(100)
+            .stop_price(95)
+            .build()
+            .unwrap();
+        let original = intent.clone();
+        intent
+            .apply_patch(&serde_json::json!({
+                "limit_price": "101",
+                "stop_price": null,
+                "amount": {"shares": "20"},
+                "id": original.id,
+            }))
+            .unwrap();
+        assert_eq!(intent.limit_price, Some(Decimal::new(101, 0)));
+        assert_eq!(intent.stop_price, None);
+        assert_eq!(intent.amount, Amount::Shares(Decimal::new(20, 0)));
+        assert_eq!(intent.id, original.id);
+
+        let mut patched = original.clone();
+        assert!(matches!(
+            patched.apply_patch(&serde_json::json!({"id": Uuid::new_v4()})),
+            Err(Error::InvalidPatch(_))
+        ));
+        assert!(matches!(
+            patched.apply_patch(&serde_json::json!({"limit_prise": "1"})),
+            Err(Error::InvalidPatch(_))
+        ));
+        assert!(matches!(
+            patched.apply_patch(&serde_json::json!({
+                "identifier": "MSFT",
+                "strategy": "B",
+                "timestamp": "2000-01-01T00:00:00Z",
+            })),
+            Err(Error::InvalidPatch(_))
+        ));
+        assert!(matches!(
+            patched.apply_patch(&serde_json::json!({"before": "2000-01-01T00:00:00Z", "after": "2000-01-02T00:00:00Z"})),
+            Err(Error::InvalidBeforeAfter(_, _))
+        ));
+        assert_eq!(patched, original);
+    }
+
+    #[test]
+    fn can_query_amount_unit() {
+        let cases = [
+            (Amount::Dollars(Decimal::ONE), AmountUnit::Dollars),
+            (Amount::Shares(Decimal::ONE), AmountUnit::Shares),
+            (Amount::Shares(Decimal::ZERO), AmountUnit::Shares),
+            (Amount::Zero, AmountUnit::Zero),
+        ];
+        for (amount, unit) in cases {
+            let intent = PositionIntent::builder("A", "AAPL", amount)
+                .build()
+                .unwrap();
+            assert_eq!(intent.amount_unit(), unit);
+        }
+    }
 }
@@ -1,5 +1,13 @@
-use rust_decimal::Decimal;
+use crate::{Error, ExecutionReport, OrderStatus};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::BufRead;
+use std::ops::Range;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -16,66 +24,920 @@ pub enum OrderType {
         stop_price: Decimal,
         limit_price: Decimal,
     },
+    /// A limit order whose price tracks the market, offset by `offset`.
+    PeggedToMarket {
+        offset: Decimal,
+    },
+    /// A market order executed in the opening auction. Must be paired with `TimeInForce::Open`.
+    MarketOnOpen,
+    /// A market order executed in the closing auction. Must be paired with `TimeInForce::Close`.
+    MarketOnClose,
+}
+
+/// Coarse category of an [`OrderType`], for reporting.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SimplifiedOrderType {
+    Market,
+    Limit,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl OrderType {
+    /// A `Limit` order, rejecting non-positive prices.
+    pub fn try_limit(limit_price: Decimal) -> Result<OrderType, Error> {
+        Ok(OrderType::Limit {
+            limit_price: positive(limit_price)?,
+        })
+    }
+
+    /// A `Stop` order, rejecting non-positive prices.
+    pub fn try_stop(stop_price: Decimal) -> Result<OrderType, Error> {
+        Ok(OrderType::Stop {
+            stop_price: positive(stop_price)?,
+        })
+    }
+
+    /// A `StopLimit` order, rejecting non-positive prices.
+    pub fn try_stop_limit(stop_price: Decimal, limit_price: Decimal) -> Result<OrderType, Error> {
+        Ok(OrderType::StopLimit {
+            stop_price: positive(stop_price)?,
+            limit_price: positive(limit_price)?,
+        })
+    }
+
+    /// Collapse into a [`SimplifiedOrderType`]. Auction orders count as `Market`, pegged orders
+    /// as `Limit`, and both stop variants as `Stop`.
+    pub fn simplified(&self) -> SimplifiedOrderType {
+        match self {
+            OrderType::Market | OrderType::MarketOnOpen | OrderType::MarketOnClose => {
+                SimplifiedOrderType::Market
+            }
+            OrderType::Limit { .. } | OrderType::PeggedToMarket { .. } => {
+                SimplifiedOrderType::Limit
+            }
+            OrderType::Stop { .. } | OrderType::StopLimit { .. } => SimplifiedOrderType::Stop,
+        }
+    }
+
+    /// A copy with every price normalized to its minimal scale, so that e.g. `3.0` becomes `3`.
+    /// Prices already compare equal regardless of scale, but serialize differently.
+    pub fn normalized(&self) -> OrderType {
+        match self {
+            OrderType::Limit { limit_price } => OrderType::Limit {
+                limit_price: limit_price.normalize(),
+            },
+            OrderType::Stop { stop_price } => OrderType::Stop {
+                stop_price: stop_price.normalize(),
+            },
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => OrderType::StopLimit {
+                stop_price: stop_price.normalize(),
+                limit_price: limit_price.normalize(),
+            },
+            OrderType::PeggedToMarket { offset } => OrderType::PeggedToMarket {
+                offset: offset.normalize(),
+            },
+            OrderType::Market | OrderType::MarketOnOpen | OrderType::MarketOnClose => self.clone(),
+        }
+    }
+
+    /// A `Limit` order `bps` basis points through `reference`: above it when buying and below it
+    /// when selling. Negative `bps` place the limit on the passive side of `reference` instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if computing the limit overflows a `Decimal`, which only happens for references
+    /// far beyond any real price.
+    pub fn limit_bps(reference: Decimal, bps: i32, side: Side) -> OrderType {
+        let offset = reference * Decimal::from(bps) / Decimal::from(10_000);
+        let limit_price = match side {
+            Side::Buy => reference + offset,
+            Side::Sell => reference - offset,
+        };
+        OrderType::Limit { limit_price }
+    }
+
+    /// Flatten into string entries for key-value stores, e.g. `{"order_type": "stop_limit",
+    /// "stop_price": "100", "limit_price": "101"}`. Keys match the JSON field names.
+    pub fn to_flat_map(&self) -> HashMap<String, String> {
+        let (name, prices): (&str, Vec<(&str, &Decimal)>) = match self {
+            OrderType::Market => ("market", vec![]),
+            OrderType::Limit { limit_price } => ("limit", vec![("limit_price", limit_price)]),
+            OrderType::Stop { stop_price } => ("stop", vec![("stop_price", stop_price)]),
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => (
+                "stop_limit",
+                vec![("stop_price", stop_price), ("limit_price", limit_price)],
+            ),
+            OrderType::PeggedToMarket { offset } => ("pegged_to_market", vec![("offset", offset)]),
+            OrderType::MarketOnOpen => ("market_on_open", vec![]),
+            OrderType::MarketOnClose => ("market_on_close", vec![]),
+        };
+        let mut map = HashMap::new();
+        map.insert("order_type".to_string(), name.to_string());
+        for (key, price) in prices {
+            map.insert(key.to_string(), price.to_string());
+        }
+        map
+    }
+
+    /// Inverse of [`OrderType::to_flat_map`]. Entries not used by the order type are ignored.
+    pub fn from_flat_map(map: &HashMap<String, String>) -> Result<OrderType, Error> {
+        let price = |key: &str| {
+            let value = map
+                .get(key)
+                .ok_or_else(|| Error::ParseOrderType(format!("missing `{}`", key)))?;
+            value
+                .parse::<Decimal>()
+                .map_err(|_| Error::ParseOrderType(format!("invalid {} `{}`", key, value)))
+        };
+        let name = map
+            .get("order_type")
+            .ok_or_else(|| Error::ParseOrderType("missing `order_type`".into()))?;
+        match name.as_str() {
+            "market" => Ok(OrderType::Market),
+            "limit" => Ok(OrderType::Limit {
+                limit_price: price("limit_price")?,
+            }),
+            "stop" => Ok(OrderType::Stop {
+                stop_price: price("stop_price")?,
+            }),
+            "stop_limit" => Ok(OrderType::StopLimit {
+                stop_price: price("stop_price")?,
+                limit_price: price("limit_price")?,
+            }),
+            "pegged_to_market" => Ok(OrderType::PeggedToMarket {
+                offset: price("offset")?,
+            }),
+            "market_on_open" => Ok(OrderType::MarketOnOpen),
+            "market_on_close" => Ok(OrderType::MarketOnClose),
+            _ => Err(Error::ParseOrderType(format!(
+                "unknown order type `{}`",
+                name
+            ))),
+        }
+    }
+}
+
+fn positive(price: Decimal) -> Result<Decimal, Error> {
+    if price <= Decimal::ZERO {
+        return Err(Error::InvalidPrice(price));
+    }
+    Ok(price)
+}
+
+/// Parses the shorthand `market`, `market_on_open`, `market_on_close`, `limit:<price>`,
+/// `stop:<price>`, `stop_limit:<stop_price>:<limit_price>` and `pegged_to_market:<offset>`.
+impl TryFrom<&str> for OrderType {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let price = |p: &str| {
+            p.parse::<Decimal>()
+                .map_err(|_| Error::ParseOrderType(format!("invalid price `{}` in `{}`", p, s)))
+        };
+        let parts: Vec<&str> = s.split(':').collect();
+        match parts.as_slice() {
+            ["market"] => Ok(OrderType::Market),
+            ["market_on_open"] => Ok(OrderType::MarketOnOpen),
+            ["market_on_close"] => Ok(OrderType::MarketOnClose),
+            ["limit", limit_price] => Ok(OrderType::Limit {
+                limit_price: price(limit_price)?,
+            }),
+            ["stop", stop_price] => Ok(OrderType::Stop {
+                stop_price: price(stop_price)?,
+            }),
+            ["stop_limit", stop_price, limit_price] => Ok(OrderType::StopLimit {
+                stop_price: price(stop_price)?,
+                limit_price: price(limit_price)?,
+            }),
+            ["pegged_to_market", offset] => Ok(OrderType::PeggedToMarket {
+                offset: price(offset)?,
+            }),
+            ["market", ..]
+            | ["market_on_open", ..]
+            | ["market_on_close", ..]
+            | ["limit", ..]
+            | ["stop", ..]
+            | ["stop_limit", ..]
+            | ["pegged_to_market", ..] => Err(Error::ParseOrderType(format!(
+                "wrong number of prices in `{}`",
+                s
+            ))),
+            _ => Err(Error::ParseOrderType(format!("unknown order type `{}`", s))),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum TimeInForce {
-    #[serde(rename = "gtc")]
+    #[serde(rename = "gtc", alias = "GTC")]
     GoodTilCanceled,
-    #[serde(rename = "day")]
+    #[serde(rename = "day", alias = "DAY")]
     Day,
-    #[serde(rename = "ioc")]
+    #[serde(rename = "ioc", alias = "IOC")]
     ImmediateOrCancel,
-    #[serde(rename = "fok")]
+    #[serde(rename = "fok", alias = "FOK")]
     FillOrKill,
-    #[serde(rename = "opg")]
+    #[serde(rename = "opg", alias = "OPG")]
     Open,
-    #[serde(rename = "cls")]
+    #[serde(rename = "cls", alias = "CLS")]
     Close,
 }
 
+/// Alternate wire representation of [`TimeInForce`] using uppercase codes (`GTC`, `DAY`, ...).
+/// Both cases are accepted when deserializing.
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(transparent)]
+pub struct UppercaseTimeInForce(pub TimeInForce);
+
+impl Serialize for UppercaseTimeInForce {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let code = match self.0 {
+            TimeInForce::GoodTilCanceled => "GTC",
+            TimeInForce::Day => "DAY",
+            TimeInForce::ImmediateOrCancel => "IOC",
+            TimeInForce::FillOrKill => "FOK",
+            TimeInForce::Open => "OPG",
+            TimeInForce::Close => "CLS",
+        };
+        serializer.serialize_str(code)
+    }
+}
+
+/// Maximum length, in characters, of a client order id accepted by brokers.
+pub const MAX_CLIENT_ORDER_ID_LENGTH: usize = 48;
+
+impl TimeInForce {
+    /// Rough time at which an order submitted at `from` with this time in force expires, for
+    /// simulation. Assumes the order is submitted during the session ending at `session_close`,
+    /// and that `Open` orders are submitted just before the opening auction, so they expire
+    /// immediately if not filled there. Returns `None` for `GoodTilCanceled`, which never expires.
+    pub fn approximate_expiry(
+        &self,
+        from: DateTime<Utc>,
+        session_close: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            TimeInForce::GoodTilCanceled => None,
+            TimeInForce::Day | TimeInForce::Close => Some(session_close),
+            TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill | TimeInForce::Open => {
+                Some(from)
+            }
+        }
+    }
+}
+
+/// An order to trade `qty` shares of `ticker` on the given `side`.
+///
+/// Intents serialized before `side` existed carry a signed `qty` instead, with negative
+/// quantities denoting sells. These are still accepted when deserializing.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(try_from = "RawTradeIntent")]
 pub struct TradeIntent {
     pub id: Uuid,
+    /// Order id sent to the broker, distinct from our internal `id`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
     pub ticker: String,
-    pub qty: isize,
+    pub side: Side,
+    /// Unsigned number of shares; see [`TradeIntent::qty`] for the signed quantity.
+    qty: usize,
     #[serde(flatten)]
     pub order_type: OrderType,
     pub time_in_force: TimeInForce,
+    /// Explicit expiry carried by systems that separate it from the time in force. Only valid
+    /// with `TimeInForce::GoodTilCanceled`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub good_til_date: Option<DateTime<Utc>>,
+    /// Whether the order may execute outside regular trading hours. Only `Limit` orders are
+    /// accepted by brokers in extended-hours sessions.
+    #[serde(default)]
+    pub extended_hours: bool,
+    /// Whether the order may only reduce an existing position, never open or flip one.
+    #[serde(default)]
+    pub reduce_only: bool,
+    /// Preferred execution venue, passed through to the execution layer as a routing hint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub venue: Option<String>,
+}
+
+/// Wire form of [`TradeIntent`], accepting both the current `side` + unsigned `qty` layout and
+/// the legacy signed `qty`.
+#[derive(Deserialize)]
+struct RawTradeIntent {
+    id: Uuid,
+    client_order_id: Option<String>,
+    #[serde(deserialize_with = "crate::limits::bounded_string")]
+    ticker: String,
+    side: Option<Side>,
+    qty: isize,
+    #[serde(flatten)]
+    order_type: OrderType,
+    time_in_force: TimeInForce,
+    good_til_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    extended_hours: bool,
+    #[serde(default)]
+    reduce_only: bool,
+    venue: Option<String>,
 }
 
+impl TryFrom<RawTradeIntent> for TradeIntent {
+    type Error = String;
+
+    fn try_from(raw: RawTradeIntent) -> Result<Self, Self::Error> {
+        let side = match raw.side {
+            Some(_) if raw.qty < 0 => {
+                return Err(format!(
+                    "qty must be non-negative when side is given, got {}",
+                    raw.qty
+                ))
+            }
+            Some(side) => side,
+            None if raw.qty < 0 => Side::Sell,
+            None => Side::Buy,
+        };
+        Ok(TradeIntent {
+            id: raw.id,
+            client_order_id: raw.client_order_id,
+            ticker: raw.ticker,
+            side,
+            qty: raw.qty.unsigned_abs().min(MAX_QTY),
+            order_type: raw.order_type,
+            time_in_force: raw.time_in_force,
+            good_til_date: raw.good_til_date,
+            extended_hours: raw.extended_hours,
+            reduce_only: raw.reduce_only,
+            venue: raw.venue,
+        })
+    }
+}
+
+/// Largest quantity an intent can carry, so that the signed [`TradeIntent::qty`] can't wrap.
+const MAX_QTY: usize = isize::MAX as usize;
+
 impl TradeIntent {
-    pub fn new(ticker: impl Into<String>, qty: isize) -> Self {
+    /// Quantities above `isize::MAX` are capped to it, so that the signed quantity stays
+    /// representable.
+    pub fn new(ticker: impl Into<String>, side: Side, qty: usize) -> Self {
         Self {
             id: Uuid::new_v4(),
+            client_order_id: None,
             ticker: ticker.into(),
-            qty,
+            side,
+            qty: qty.min(MAX_QTY),
             order_type: OrderType::Market,
             time_in_force: TimeInForce::Day,
+            good_til_date: None,
+            extended_hours: false,
+            reduce_only: false,
+            venue: None,
+        }
+    }
+
+    /// Construct an intent from a signed quantity, where negative quantities are sells. Eases
+    /// migrating code written against the old `TradeIntent::new(ticker, qty: isize)`.
+    pub fn from_signed_qty(ticker: impl Into<String>, qty: isize) -> Self {
+        let side = if qty < 0 { Side::Sell } else { Side::Buy };
+        Self::new(ticker, side, qty.unsigned_abs())
+    }
+
+    /// House preset for aggressive execution: a `Market` order with `ImmediateOrCancel`.
+    pub fn aggressive(ticker: impl Into<String>, side: Side, qty: usize) -> Self {
+        Self::new(ticker, side, qty)
+            .market()
+            .time_in_force(TimeInForce::ImmediateOrCancel)
+    }
+
+    /// House preset for passive execution: a `Limit` order at `limit_price`, good for the `Day`.
+    pub fn passive(
+        ticker: impl Into<String>,
+        side: Side,
+        qty: usize,
+        limit_price: impl Into<Decimal>,
+    ) -> Self {
+        Self::new(ticker, side, qty)
+            .limit(limit_price)
+            .time_in_force(TimeInForce::Day)
+    }
+
+    /// Construct an intent for `notional` dollars at `price`, rounding the quantity to the nearest
+    /// whole share with midpoints away from zero. Negative notionals and non-positive prices are
+    /// rejected.
+    pub fn from_notional(
+        ticker: impl Into<String>,
+        notional: Decimal,
+        price: Decimal,
+        side: Side,
+    ) -> Result<Self, Error> {
+        let price = positive(price)?;
+        let invalid = || Error::InvalidAmount(notional.to_string());
+        if notional.is_sign_negative() {
+            return Err(invalid());
+        }
+        let qty = notional
+            .checked_div(price)
+            .ok_or_else(invalid)?
+            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+            .to_usize()
+            .ok_or_else(invalid)?;
+        Ok(Self::new(ticker, side, qty))
+    }
+
+    /// The signed quantity: positive for buys and negative for sells.
+    pub fn qty(&self) -> isize {
+        match self.side {
+            Side::Buy => isize::try_from(self.qty).unwrap_or(isize::MAX),
+            Side::Sell => isize::try_from(self.qty).map_or(-isize::MAX, |qty| -qty),
         }
     }
 
+    /// The unsigned number of shares to trade.
+    pub fn abs_qty(&self) -> usize {
+        self.qty
+    }
+
+    /// Quantities above `isize::MAX` are capped, as in [`TradeIntent::new`].
+    pub fn with_qty(mut self, qty: usize) -> Self {
+        self.qty = qty.min(MAX_QTY);
+        self
+    }
+
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
     pub fn id(mut self, id: Uuid) -> Self {
         self.id = id;
         self
     }
 
+    pub fn client_order_id(mut self, client_order_id: impl Into<String>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    /// Set the client order id to the hyphenated form of `id`.
+    pub fn client_order_id_from_id(mut self) -> Self {
+        self.client_order_id = Some(self.id.to_hyphenated().to_string());
+        self
+    }
+
     pub fn order_type(mut self, order_type: OrderType) -> Self {
         self.order_type = order_type;
         self
     }
 
+    pub fn market(mut self) -> Self {
+        self.order_type = OrderType::Market;
+        self
+    }
+
+    pub fn limit(mut self, limit_price: impl Into<Decimal>) -> Self {
+        self.order_type = OrderType::Limit {
+            limit_price: limit_price.into(),
+        };
+        self
+    }
+
+    pub fn stop(mut self, stop_price: impl Into<Decimal>) -> Self {
+        self.order_type = OrderType::Stop {
+            stop_price: stop_price.into(),
+        };
+        self
+    }
+
+    pub fn stop_limit(
+        mut self,
+        stop_price: impl Into<Decimal>,
+        limit_price: impl Into<Decimal>,
+    ) -> Self {
+        self.order_type = OrderType::StopLimit {
+            stop_price: stop_price.into(),
+            limit_price: limit_price.into(),
+        };
+        self
+    }
+
+    pub fn peg_to_market(mut self, offset: impl Into<Decimal>) -> Self {
+        self.order_type = OrderType::PeggedToMarket {
+            offset: offset.into(),
+        };
+        self
+    }
+
+    /// Use a `MarketOnOpen` order, setting the matching `TimeInForce::Open`.
+    pub fn market_on_open(mut self) -> Self {
+        self.order_type = OrderType::MarketOnOpen;
+        self.time_in_force = TimeInForce::Open;
+        self
+    }
+
+    /// Use a `MarketOnClose` order, setting the matching `TimeInForce::Close`.
+    pub fn market_on_close(mut self) -> Self {
+        self.order_type = OrderType::MarketOnClose;
+        self.time_in_force = TimeInForce::Close;
+        self
+    }
+
     pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
         self.time_in_force = time_in_force;
         self
     }
+
+    pub fn good_til_date(mut self, good_til_date: DateTime<Utc>) -> Self {
+        self.good_til_date = Some(good_til_date);
+        self
+    }
+
+    pub fn extended_hours(mut self, extended_hours: bool) -> Self {
+        self.extended_hours = extended_hours;
+        self
+    }
+
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    pub fn venue(mut self, venue: impl Into<String>) -> Self {
+        self.venue = Some(venue.into());
+        self
+    }
+
+    /// FIX tag/value pairs describing the order: ClOrdID (11, when set), Symbol (55), Side (54),
+    /// OrderQty (38), OrdType (40), Price (44), StopPx (99), PegOffsetValue (211) and
+    /// TimeInForce (59). Price fields are only emitted for order types that use them. Auction
+    /// orders are sent as market orders, with the session conveyed by the time in force.
+    pub fn to_fix_fields(&self) -> Vec<(u32, String)> {
+        let mut fields = Vec::new();
+        if let Some(client_order_id) = &self.client_order_id {
+            fields.push((11, client_order_id.clone()));
+        }
+        fields.push((55, self.ticker.clone()));
+        let side = match self.side {
+            Side::Buy => "1",
+            Side::Sell => "2",
+        };
+        fields.push((54, side.into()));
+        fields.push((38, self.qty.to_string()));
+        match &self.order_type {
+            OrderType::Market | OrderType::MarketOnOpen | OrderType::MarketOnClose => {
+                fields.push((40, "1".into()))
+            }
+            OrderType::Limit { limit_price } => {
+                fields.push((40, "2".into()));
+                fields.push((44, limit_price.to_string()));
+            }
+            OrderType::Stop { stop_price } => {
+                fields.push((40, "3".into()));
+                fields.push((99, stop_price.to_string()));
+            }
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => {
+                fields.push((40, "4".into()));
+                fields.push((44, limit_price.to_string()));
+                fields.push((99, stop_price.to_string()));
+            }
+            OrderType::PeggedToMarket { offset } => {
+                fields.push((40, "P".into()));
+                fields.push((211, offset.to_string()));
+            }
+        }
+        let time_in_force = match self.time_in_force {
+            TimeInForce::Day => "0",
+            TimeInForce::GoodTilCanceled => "1",
+            TimeInForce::Open => "2",
+            TimeInForce::ImmediateOrCancel => "3",
+            TimeInForce::FillOrKill => "4",
+            TimeInForce::Close => "7",
+        };
+        fields.push((59, time_in_force.into()));
+        fields
+    }
+
+    /// Equality ignoring the identifying fields `id` and `client_order_id`.
+    pub fn semantically_eq(&self, other: &TradeIntent) -> bool {
+        self.ticker == other.ticker
+            && self.side == other.side
+            && self.qty == other.qty
+            && self.order_type == other.order_type
+            && self.time_in_force == other.time_in_force
+            && self.good_til_date == other.good_til_date
+            && self.extended_hours == other.extended_hours
+            && self.reduce_only == other.reduce_only
+            && self.venue == other.venue
+    }
+
+    /// Simulate executing the intent against a market trading at `price`, for backtesting. The
+    /// intent is filled in full at `price` if its order type would execute there, otherwise the
+    /// report has status `New` with nothing filled.
+    pub fn simulate_fill(&self, price: Decimal) -> ExecutionReport {
+        let is_buy = self.side == Side::Buy;
+        let limit_reached = |limit_price: Decimal| {
+            if is_buy {
+                price <= limit_price
+            } else {
+                price >= limit_price
+            }
+        };
+        let stop_reached = |stop_price: Decimal| {
+            if is_buy {
+                price >= stop_price
+            } else {
+                price <= stop_price
+            }
+        };
+        let fills = match self.order_type {
+            OrderType::Limit { limit_price } => limit_reached(limit_price),
+            OrderType::Stop { stop_price } => stop_reached(stop_price),
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => stop_reached(stop_price) && limit_reached(limit_price),
+            OrderType::Market
+            | OrderType::PeggedToMarket { .. }
+            | OrderType::MarketOnOpen
+            | OrderType::MarketOnClose => true,
+        };
+        let (status, filled_qty, fill_price) = if fills {
+            (OrderStatus::Filled, self.qty(), Some(price))
+        } else {
+            (OrderStatus::New, 0, None)
+        };
+        ExecutionReport {
+            intent_id: self.id,
+            ticker: self.ticker.clone(),
+            status,
+            filled_qty,
+            fill_price,
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.extended_hours && !matches!(self.order_type, OrderType::Limit { .. }) {
+            return Err(Error::InvalidExtendedHours(self.order_type.clone()));
+        }
+        match (&self.order_type, &self.time_in_force) {
+            (OrderType::MarketOnOpen, TimeInForce::Open) => (),
+            (OrderType::MarketOnClose, TimeInForce::Close) => (),
+            (OrderType::MarketOnOpen, _) | (OrderType::MarketOnClose, _) => {
+                return Err(Error::InvalidTimeInForce(
+                    self.order_type.clone(),
+                    self.time_in_force.clone(),
+                ))
+            }
+            _ => (),
+        }
+        if self.good_til_date.is_some() && self.time_in_force != TimeInForce::GoodTilCanceled {
+            return Err(Error::InvalidGoodTilDate(self.time_in_force.clone()));
+        }
+        if let Some(client_order_id) = &self.client_order_id {
+            if client_order_id.chars().count() > MAX_CLIENT_ORDER_ID_LENGTH {
+                return Err(Error::InvalidClientOrderId(
+                    client_order_id.clone(),
+                    MAX_CLIENT_ORDER_ID_LENGTH,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every limit and stop price of the order is a multiple of the exchange `tick`,
+    /// e.g. `0.01`. The offset of a `PeggedToMarket` order is not a price and isn't checked.
+    pub fn validate_tick(&self, tick: Decimal) -> Result<(), Error> {
+        let tick = positive(tick)?;
+        let prices = match &self.order_type {
+            OrderType::Limit { limit_price } => vec![*limit_price],
+            OrderType::Stop { stop_price } => vec![*stop_price],
+            OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            } => vec![*stop_price, *limit_price],
+            OrderType::Market
+            | OrderType::PeggedToMarket { .. }
+            | OrderType::MarketOnOpen
+            | OrderType::MarketOnClose => vec![],
+        };
+        match prices.into_iter().find(|price| !(price % tick).is_zero()) {
+            Some(price) => Err(Error::InvalidTick(price, tick)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Alternate wire representation of a [`TradeIntent`] with the order type nested under an
+/// `order` object, e.g. `"order": {"order_type": "limit", "limit_price": "100"}`, instead of
+/// flattened into the intent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NestedTradeIntent(pub TradeIntent);
+
+impl Serialize for NestedTradeIntent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error as _;
+        let object = |value| match value {
+            Value::Object(object) => Ok(object),
+            _ => Err(S::Error::custom("expected a JSON object")),
+        };
+        let mut intent = object(serde_json::to_value(&self.0).map_err(S::Error::custom)?)?;
+        let order = object(serde_json::to_value(&self.0.order_type).map_err(S::Error::custom)?)?;
+        for key in order.keys() {
+            intent.remove(key);
+        }
+        intent.insert("order".into(), Value::Object(order));
+        intent.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NestedTradeIntent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+        let mut intent = Map::deserialize(deserializer)?;
+        match intent.remove("order") {
+            Some(Value::Object(order)) => intent.extend(order),
+            Some(_) => return Err(D::Error::custom("`order` must be an object")),
+            None => return Err(D::Error::missing_field("order")),
+        }
+        TradeIntent::deserialize(Value::Object(intent))
+            .map(NestedTradeIntent)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Two exit orders for the same position where filling one cancels the other. Both legs close
+/// the same position, so they must trade the same ticker on the same side.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OcoGroup {
+    pub take_profit: TradeIntent,
+    pub stop_loss: TradeIntent,
+}
+
+impl OcoGroup {
+    pub fn new(take_profit: TradeIntent, stop_loss: TradeIntent) -> Result<Self, Error> {
+        let group = Self {
+            take_profit,
+            stop_loss,
+        };
+        group.validate()?;
+        Ok(group)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.take_profit.ticker != self.stop_loss.ticker {
+            return Err(Error::InvalidOcoGroup(format!(
+                "legs trade different tickers: {} and {}",
+                self.take_profit.ticker, self.stop_loss.ticker
+            )));
+        }
+        if self.take_profit.side != self.stop_loss.side {
+            return Err(Error::InvalidOcoGroup(
+                "legs trade on opposite sides".into(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(tag = "action", rename_all = "snake_case")]
 pub enum TradeMessage {
-    New { intent: TradeIntent },
-    Cancel { id: Uuid },
+    New {
+        intent: TradeIntent,
+    },
+    Cancel {
+        id: Uuid,
+    },
+    /// Several intents to be submitted as a unit. Consumers must either accept every intent in
+    /// the batch or reject the batch as a whole.
+    Batch {
+        intents: Vec<TradeIntent>,
+    },
+    /// Acknowledges receipt of the message concerning `id`, with a `reason` when it was rejected.
+    Ack {
+        id: Uuid,
+        accepted: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+}
+
+impl TradeMessage {
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            TradeMessage::New { intent } => intent.validate(),
+            TradeMessage::Cancel { .. } | TradeMessage::Ack { .. } => Ok(()),
+            TradeMessage::Batch { intents } => {
+                if intents.is_empty() {
+                    return Err(Error::EmptyBatch);
+                }
+                intents.iter().try_for_each(TradeIntent::validate)
+            }
+        }
+    }
+
+    /// Cost of processing the message, for rate limiting: one per intent in a `Batch`, and one
+    /// for every other message.
+    pub fn weight(&self) -> u32 {
+        match self {
+            TradeMessage::Batch { intents } => u32::try_from(intents.len()).unwrap_or(u32::MAX),
+            TradeMessage::New { .. } | TradeMessage::Cancel { .. } | TradeMessage::Ack { .. } => 1,
+        }
+    }
+}
+
+/// Parse a stream of newline-delimited JSON [`TradeMessage`]s, yielding one result per non-blank
+/// line. Read errors are reported as `serde_json` I/O errors.
+pub fn read_messages<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<TradeMessage, serde_json::Error>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(serde_json::from_str(&line)),
+        Err(error) => Some(Err(serde_json::Error::io(error))),
+    })
+}
+
+/// Tracing metadata wrapped around a [`TradeMessage`] as it travels between services.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TradeEnvelope {
+    /// Shared by every message belonging to the same logical flow.
+    pub correlation_id: Uuid,
+    /// Name of the service that sent the message.
+    pub source: String,
+    pub sent_at: DateTime<Utc>,
+    /// Per-sender sequence number, for detecting dropped messages with a [`SequenceTracker`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    pub message: TradeMessage,
+}
+
+impl TradeEnvelope {
+    /// Wrap `message` in a new envelope with a fresh correlation id.
+    pub fn new(source: impl Into<String>, message: TradeMessage) -> Self {
+        Self::with_correlation_id(Uuid::new_v4(), source, message)
+    }
+
+    /// Wrap `message` in an envelope continuing an existing flow.
+    pub fn with_correlation_id(
+        correlation_id: Uuid,
+        source: impl Into<String>,
+        message: TradeMessage,
+    ) -> Self {
+        Self {
+            correlation_id,
+            source: source.into(),
+            sent_at: Utc::now(),
+            sequence: None,
+            message,
+        }
+    }
+
+    pub fn sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+}
+
+/// Detects gaps in the sequence numbers of a stream of messages from one sender.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceTracker {
+    next: Option<u64>,
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the message with `sequence` was received, returning the range of sequence
+    /// numbers that were skipped since the previous message, if any. Duplicate or out-of-order
+    /// sequence numbers are ignored.
+    pub fn observe(&mut self, sequence: u64) -> Option<Range<u64>> {
+        let gap = match self.next {
+            Some(next) if sequence < next => return None,
+            Some(next) if sequence > next => Some(next..sequence),
+            _ => None,
+        };
+        self.next = Some(sequence.saturating_add(1));
+        gap
+    }
 }
 
 #[cfg(test)]
@@ -84,7 +946,7 @@ mod test {
 
     #[test]
     fn can_serialize_and_deserialize() {
-        let intent = TradeIntent::new("AAPL", 10)
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10)
             .id(Uuid::new_v4())
             .order_type(OrderType::StopLimit {
                 stop_price: Decimal::new(100, 0),
@@ -102,4 +964,814 @@ mod test {
         assert_eq!(new_message, new_deserialized);
         assert_eq!(cancel_message, cancel_deserialized);
     }
+
+    #[test]
+    fn extended_hours_defaults_to_false() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10)
+            .extended_hours(true)
+            .order_type(OrderType::Limit {
+                limit_price: Decimal::new(100, 0),
+            });
+        let serialized = serde_json::to_string(&intent).unwrap();
+        let deserialized: TradeIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(intent, deserialized);
+
+        let data = r#"{"id":"7b2c5f2c-5c1e-4f5b-8d3a-6a8f1b7e9c10","ticker":"AAPL","qty":10,"order_type":"market","time_in_force":"day"}"#;
+        let deserialized: TradeIntent = serde_json::from_str(data).unwrap();
+        assert!(!deserialized.extended_hours);
+    }
+
+    #[test]
+    fn extended_hours_requires_limit_order() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).extended_hours(true);
+        assert!(matches!(
+            intent.validate(),
+            Err(Error::InvalidExtendedHours(OrderType::Market))
+        ));
+        let intent = intent.order_type(OrderType::Limit {
+            limit_price: Decimal::new(100, 0),
+        });
+        assert!(intent.validate().is_ok());
+        assert!(TradeIntent::new("AAPL", Side::Buy, 10).validate().is_ok());
+    }
+
+    #[test]
+    fn can_parse_order_type() {
+        assert_eq!(OrderType::try_from("market").unwrap(), OrderType::Market);
+        assert_eq!(
+            OrderType::try_from("limit:100.5").unwrap(),
+            OrderType::Limit {
+                limit_price: Decimal::new(1005, 1)
+            }
+        );
+        assert_eq!(
+            OrderType::try_from("stop:99").unwrap(),
+            OrderType::Stop {
+                stop_price: Decimal::new(99, 0)
+            }
+        );
+        assert_eq!(
+            OrderType::try_from("stop_limit:100:101").unwrap(),
+            OrderType::StopLimit {
+                stop_price: Decimal::new(100, 0),
+                limit_price: Decimal::new(101, 0)
+            }
+        );
+    }
+
+    #[test]
+    fn parsing_malformed_order_type_fails() {
+        assert!(matches!(
+            OrderType::try_from("limit:abc"),
+            Err(Error::ParseOrderType(_))
+        ));
+        assert!(matches!(
+            OrderType::try_from("stop_limit:100"),
+            Err(Error::ParseOrderType(_))
+        ));
+        assert!(matches!(
+            OrderType::try_from("trailing_stop:1"),
+            Err(Error::ParseOrderType(_))
+        ));
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_pegged_order() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).peg_to_market(Decimal::new(-5, 2));
+        assert_eq!(
+            intent.order_type,
+            OrderType::PeggedToMarket {
+                offset: Decimal::new(-5, 2)
+            }
+        );
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert_eq!(serialized["order_type"], "pegged_to_market");
+        assert_eq!(serialized["offset"], "-0.05");
+        let deserialized: TradeIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(intent, deserialized);
+        assert_eq!(
+            OrderType::try_from("pegged_to_market:-0.05").unwrap(),
+            intent.order_type
+        );
+    }
+
+    #[test]
+    fn can_serialize_client_order_id() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert!(serialized.get("client_order_id").is_none());
+
+        let intent = intent.client_order_id("my-order-1");
+        let serialized = serde_json::to_string(&intent).unwrap();
+        let deserialized: TradeIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.client_order_id, Some("my-order-1".into()));
+
+        let intent = intent.client_order_id_from_id();
+        assert_eq!(intent.client_order_id, Some(intent.id.to_string()));
+    }
+
+    #[test]
+    fn client_order_id_length_is_validated() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).client_order_id("A".repeat(48));
+        assert!(intent.validate().is_ok());
+        let intent = intent.client_order_id("A".repeat(49));
+        assert!(matches!(
+            intent.validate(),
+            Err(Error::InvalidClientOrderId(_, MAX_CLIENT_ORDER_ID_LENGTH))
+        ));
+        let intent = intent.client_order_id("é".repeat(48));
+        assert!(intent.validate().is_ok());
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_envelope() {
+        let envelope = TradeEnvelope::new(
+            "strategy-runner",
+            TradeMessage::New {
+                intent: TradeIntent::new("AAPL", Side::Buy, 10),
+            },
+        );
+        let reply = TradeEnvelope::with_correlation_id(
+            envelope.correlation_id,
+            "order-manager",
+            TradeMessage::Cancel { id: Uuid::new_v4() },
+        );
+        assert_eq!(envelope.correlation_id, reply.correlation_id);
+        let serialized = serde_json::to_string(&envelope).unwrap();
+        let deserialized: TradeEnvelope = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(envelope, deserialized);
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_auction_orders() {
+        for intent in [
+            TradeIntent::new("AAPL", Side::Buy, 10).market_on_open(),
+            TradeIntent::new("AAPL", Side::Buy, 10).market_on_close(),
+        ] {
+            assert!(intent.validate().is_ok());
+            let serialized = serde_json::to_string(&intent).unwrap();
+            let deserialized: TradeIntent = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(intent, deserialized);
+        }
+        let serialized =
+            serde_json::to_value(TradeIntent::new("AAPL", Side::Buy, 10).market_on_close())
+                .unwrap();
+        assert_eq!(serialized["order_type"], "market_on_close");
+        assert_eq!(serialized["time_in_force"], "cls");
+    }
+
+    #[test]
+    fn auction_orders_require_matching_time_in_force() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).order_type(OrderType::MarketOnClose);
+        assert!(matches!(
+            intent.validate(),
+            Err(Error::InvalidTimeInForce(
+                OrderType::MarketOnClose,
+                TimeInForce::Day
+            ))
+        ));
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10)
+            .market_on_open()
+            .time_in_force(TimeInForce::Close);
+        assert!(intent.validate().is_err());
+    }
+
+    #[test]
+    fn can_serialize_venue() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert!(serialized.get("venue").is_none());
+        let deserialized: TradeIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.venue, None);
+
+        let intent = intent.venue("IEX");
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert_eq!(serialized["venue"], "IEX");
+        let deserialized: TradeIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(intent, deserialized);
+    }
+
+    #[test]
+    fn price_setters_accept_literals() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).peg_to_market(1);
+        assert_eq!(
+            intent.order_type,
+            OrderType::PeggedToMarket {
+                offset: Decimal::ONE
+            }
+        );
+    }
+
+    #[test]
+    fn can_simulate_market_fill() {
+        let intent = TradeIntent::new("AAPL", Side::Sell, 10);
+        let report = intent.simulate_fill(Decimal::new(100, 0));
+        assert_eq!(report.intent_id, intent.id);
+        assert_eq!(report.status, OrderStatus::Filled);
+        assert_eq!(report.filled_qty, -10);
+        assert_eq!(report.fill_price, Some(Decimal::new(100, 0)));
+    }
+
+    #[test]
+    fn stop_not_reached_does_not_fill() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).order_type(OrderType::Stop {
+            stop_price: Decimal::new(105, 0),
+        });
+        let report = intent.simulate_fill(Decimal::new(100, 0));
+        assert_eq!(report.status, OrderStatus::New);
+        assert_eq!(report.filled_qty, 0);
+        assert_eq!(report.fill_price, None);
+
+        let report = intent.simulate_fill(Decimal::new(106, 0));
+        assert_eq!(report.status, OrderStatus::Filled);
+        assert_eq!(report.filled_qty, 10);
+    }
+
+    #[test]
+    fn can_compute_limit_from_bps() {
+        let reference = Decimal::new(100, 0);
+        assert_eq!(
+            OrderType::limit_bps(reference, 50, Side::Buy),
+            OrderType::Limit {
+                limit_price: Decimal::new(1005, 1)
+            }
+        );
+        assert_eq!(
+            OrderType::limit_bps(reference, 50, Side::Sell),
+            OrderType::Limit {
+                limit_price: Decimal::new(995, 1)
+            }
+        );
+        assert_eq!(
+            OrderType::limit_bps(reference, -25, Side::Buy),
+            OrderType::Limit {
+                limit_price: Decimal::new(9975, 2)
+            }
+        );
+    }
+
+    #[test]
+    fn semantic_equality_ignores_id() {
+        let a = TradeIntent::new("AAPL", Side::Buy, 10).client_order_id("a");
+        let b = TradeIntent::new("AAPL", Side::Buy, 10).client_order_id("b");
+        assert_ne!(a, b);
+        assert!(a.semantically_eq(&b));
+        assert!(!a.semantically_eq(&b.time_in_force(TimeInForce::GoodTilCanceled)));
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_batch() {
+        let message = TradeMessage::Batch {
+            intents: vec![
+                TradeIntent::new("AAPL", Side::Buy, 10),
+                TradeIntent::new("MSFT", Side::Sell, 10).order_type(OrderType::Limit {
+                    limit_price: Decimal::new(300, 0),
+                }),
+            ],
+        };
+        assert!(message.validate().is_ok());
+        let serialized = serde_json::to_value(&message).unwrap();
+        assert_eq!(serialized["action"], "batch");
+        let deserialized: TradeMessage = serde_json::from_value(serialized).unwrap();
+        assert_eq!(message, deserialized);
+    }
+
+    #[test]
+    fn empty_batch_is_invalid() {
+        let message = TradeMessage::Batch { intents: vec![] };
+        assert!(matches!(message.validate(), Err(Error::EmptyBatch)));
+    }
+
+    #[test]
+    fn can_approximate_expiry() {
+        let from = Utc::now();
+        let close = from + chrono::Duration::hours(3);
+        assert_eq!(
+            TimeInForce::Day.approximate_expiry(from, close),
+            Some(close)
+        );
+        assert_eq!(
+            TimeInForce::Close.approximate_expiry(from, close),
+            Some(close)
+        );
+        assert_eq!(
+            TimeInForce::ImmediateOrCancel.approximate_expiry(from, close),
+            Some(from)
+        );
+        assert_eq!(
+            TimeInForce::FillOrKill.approximate_expiry(from, close),
+            Some(from)
+        );
+        assert_eq!(
+            TimeInForce::GoodTilCanceled.approximate_expiry(from, close),
+            None
+        );
+    }
+
+    #[test]
+    fn can_serialize_uppercase_time_in_force() {
+        let tif = UppercaseTimeInForce(TimeInForce::GoodTilCanceled);
+        assert_eq!(serde_json::to_string(&tif).unwrap(), r#""GTC""#);
+        assert_eq!(
+            serde_json::to_string(&TimeInForce::GoodTilCanceled).unwrap(),
+            r#""gtc""#
+        );
+        let tif: UppercaseTimeInForce = serde_json::from_str(r#""IOC""#).unwrap();
+        assert_eq!(tif.0, TimeInForce::ImmediateOrCancel);
+        let tif: TimeInForce = serde_json::from_str(r#""DAY""#).unwrap();
+        assert_eq!(tif, TimeInForce::Day);
+        let tif: TimeInForce = serde_json::from_str(r#""day""#).unwrap();
+        assert_eq!(tif, TimeInForce::Day);
+    }
+
+    #[test]
+    fn can_export_fix_fields() {
+        let intent = TradeIntent::new("AAPL", Side::Sell, 10)
+            .order_type(OrderType::StopLimit {
+                stop_price: Decimal::new(100, 0),
+                limit_price: Decimal::new(995, 1),
+            })
+            .time_in_force(TimeInForce::ImmediateOrCancel);
+        assert_eq!(
+            intent.to_fix_fields(),
+            vec![
+                (55, "AAPL".to_string()),
+                (54, "2".to_string()),
+                (38, "10".to_string()),
+                (40, "4".to_string()),
+                (44, "99.5".to_string()),
+                (99, "100".to_string()),
+                (59, "3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_serialize_reduce_only() {
+        let intent = TradeIntent::new("AAPL", Side::Sell, 10);
+        let mut serialized = serde_json::to_value(&intent).unwrap();
+        assert_eq!(serialized["reduce_only"], false);
+        serialized.as_object_mut().unwrap().remove("reduce_only");
+        let deserialized: TradeIntent = serde_json::from_value(serialized).unwrap();
+        assert!(!deserialized.reduce_only);
+
+        let intent = intent.reduce_only(true);
+        let serialized = serde_json::to_value(&intent).unwrap();
+        assert_eq!(serialized["reduce_only"], true);
+        let deserialized: TradeIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(intent, deserialized);
+    }
+
+    #[test]
+    fn can_normalize_order_type() {
+        let a = OrderType::StopLimit {
+            stop_price: Decimal::new(3, 0),
+            limit_price: Decimal::new(3100, 3),
+        };
+        let b = OrderType::StopLimit {
+            stop_price: Decimal::new(30, 1),
+            limit_price: Decimal::new(31, 1),
+        };
+        assert_ne!(
+            serde_json::to_string(&a).unwrap(),
+            serde_json::to_string(&b).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_string(&a.normalized()).unwrap(),
+            serde_json::to_string(&b.normalized()).unwrap()
+        );
+        let limit = OrderType::Limit {
+            limit_price: Decimal::new(300, 2),
+        };
+        assert_eq!(
+            serde_json::to_value(limit.normalized()).unwrap()["limit_price"],
+            "3"
+        );
+    }
+
+    #[test]
+    fn qty_is_signed_by_side() {
+        let buy = TradeIntent::new("AAPL", Side::Buy, 10);
+        assert_eq!(buy.qty(), 10);
+        assert_eq!(buy.abs_qty(), 10);
+        let sell = buy.side(Side::Sell).with_qty(5);
+        assert_eq!(sell.qty(), -5);
+        assert_eq!(sell.abs_qty(), 5);
+        let sell = TradeIntent::from_signed_qty("AAPL", -7);
+        assert_eq!(sell.side, Side::Sell);
+        assert_eq!(sell.abs_qty(), 7);
+
+        let serialized = serde_json::to_value(&sell).unwrap();
+        assert_eq!(serialized["side"], "sell");
+        assert_eq!(serialized["qty"], 7);
+        let deserialized: TradeIntent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(sell, deserialized);
+    }
+
+    #[test]
+    fn can_deserialize_legacy_signed_qty() {
+        let data = r#"{"id":"7b2c5f2c-5c1e-4f5b-8d3a-6a8f1b7e9c10","ticker":"AAPL","qty":-10,"order_type":"market","time_in_force":"day"}"#;
+        let intent: TradeIntent = serde_json::from_str(data).unwrap();
+        assert_eq!(intent.side, Side::Sell);
+        assert_eq!(intent.qty(), -10);
+
+        let data = r#"{"id":"7b2c5f2c-5c1e-4f5b-8d3a-6a8f1b7e9c10","ticker":"AAPL","side":"buy","qty":-10,"order_type":"market","time_in_force":"day"}"#;
+        assert!(serde_json::from_str::<TradeIntent>(data).is_err());
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_ack() {
+        let accepted = TradeMessage::Ack {
+            id: Uuid::new_v4(),
+            accepted: true,
+            reason: None,
+        };
+        let serialized = serde_json::to_value(&accepted).unwrap();
+        assert_eq!(serialized["action"], "ack");
+        assert!(serialized.get("reason").is_none());
+        let deserialized: TradeMessage = serde_json::from_value(serialized).unwrap();
+        assert_eq!(accepted, deserialized);
+
+        let rejected = TradeMessage::Ack {
+            id: Uuid::new_v4(),
+            accepted: false,
+            reason: Some("insufficient buying power".into()),
+        };
+        let serialized = serde_json::to_string(&rejected).unwrap();
+        let deserialized: TradeMessage = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(rejected, deserialized);
+    }
+
+    #[test]
+    fn checked_constructors_reject_non_positive_prices() {
+        assert_eq!(
+            OrderType::try_limit(Decimal::new(5, 0)).unwrap(),
+            OrderType::Limit {
+                limit_price: Decimal::new(5, 0)
+            }
+        );
+        assert!(OrderType::try_stop(Decimal::new(5, 0)).is_ok());
+        assert!(OrderType::try_stop_limit(Decimal::new(5, 0), Decimal::new(6, 0)).is_ok());
+        assert!(matches!(
+            OrderType::try_limit(Decimal::new(-5, 0)),
+            Err(Error::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            OrderType::try_stop(Decimal::ZERO),
+            Err(Error::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            OrderType::try_stop_limit(Decimal::new(5, 0), Decimal::new(-6, 0)),
+            Err(Error::InvalidPrice(_))
+        ));
+    }
+
+    #[test]
+    fn oco_legs_must_match() {
+        let take_profit = TradeIntent::new("AAPL", Side::Sell, 10).order_type(OrderType::Limit {
+            limit_price: Decimal::new(110, 0),
+        });
+        let stop_loss = TradeIntent::new("AAPL", Side::Sell, 10).order_type(OrderType::Stop {
+            stop_price: Decimal::new(95, 0),
+        });
+        assert!(OcoGroup::new(take_profit.clone(), stop_loss.clone()).is_ok());
+        assert!(matches!(
+            OcoGroup::new(take_profit.clone(), stop_loss.clone().side(Side::Buy)),
+            Err(Error::InvalidOcoGroup(_))
+        ));
+        let mut other_ticker = stop_loss;
+        other_ticker.ticker = "MSFT".into();
+        assert!(matches!(
+            OcoGroup::new(take_profit, other_ticker),
+            Err(Error::InvalidOcoGroup(_))
+        ));
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_oco_group() {
+        let group = OcoGroup::new(
+            TradeIntent::new("AAPL", Side::Sell, 10).order_type(OrderType::Limit {
+                limit_price: Decimal::new(110, 0),
+            }),
+            TradeIntent::new("AAPL", Side::Sell, 10).order_type(OrderType::Stop {
+                stop_price: Decimal::new(95, 0),
+            }),
+        )
+        .unwrap();
+        let serialized = serde_json::to_string(&group).unwrap();
+        let deserialized: OcoGroup = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(group, deserialized);
+    }
+
+    #[test]
+    fn can_set_order_type_with_shortcuts() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        assert_eq!(
+            intent.clone().limit(150).order_type,
+            OrderType::Limit {
+                limit_price: Decimal::new(150, 0)
+            }
+        );
+        assert_eq!(
+            intent.clone().stop(140).order_type,
+            OrderType::Stop {
+                stop_price: Decimal::new(140, 0)
+            }
+        );
+        assert_eq!(
+            intent
+                .clone()
+                .stop_limit(140, Decimal::new(1395, 1))
+                .order_type,
+            OrderType::StopLimit {
+                stop_price: Decimal::new(140, 0),
+                limit_price: Decimal::new(1395, 1),
+            }
+        );
+        assert_eq!(intent.limit(150).market().order_type, OrderType::Market);
+    }
+
+    #[test]
+    fn can_weigh_messages() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        assert_eq!(
+            TradeMessage::New {
+                intent: intent.clone()
+            }
+            .weight(),
+            1
+        );
+        assert_eq!(TradeMessage::Cancel { id: intent.id }.weight(), 1);
+        assert_eq!(
+            TradeMessage::Ack {
+                id: intent.id,
+                accepted: true,
+                reason: None
+            }
+            .weight(),
+            1
+        );
+        assert_eq!(
+            TradeMessage::Batch {
+                intents: vec![intent.clone(), intent.clone(), intent]
+            }
+            .weight(),
+            3
+        );
+    }
+
+    #[test]
+    fn can_round_trip_order_type_through_flat_map() {
+        let order_types = [
+            OrderType::Market,
+            OrderType::Limit {
+                limit_price: Decimal::new(101, 0),
+            },
+            OrderType::Stop {
+                stop_price: Decimal::new(100, 0),
+            },
+            OrderType::StopLimit {
+                stop_price: Decimal::new(100, 0),
+                limit_price: Decimal::new(1015, 1),
+            },
+            OrderType::PeggedToMarket {
+                offset: Decimal::new(-5, 2),
+            },
+            OrderType::MarketOnOpen,
+            OrderType::MarketOnClose,
+        ];
+        for order_type in order_types {
+            let map = order_type.to_flat_map();
+            assert_eq!(OrderType::from_flat_map(&map).unwrap(), order_type);
+        }
+
+        let map = OrderType::StopLimit {
+            stop_price: Decimal::new(100, 0),
+            limit_price: Decimal::new(101, 0),
+        }
+        .to_flat_map();
+        assert_eq!(map["order_type"], "stop_limit");
+        assert_eq!(map["stop_price"], "100");
+        assert_eq!(map["limit_price"], "101");
+
+        let mut missing = map;
+        missing.remove("limit_price");
+        assert!(matches!(
+            OrderType::from_flat_map(&missing),
+            Err(Error::ParseOrderType(_))
+        ));
+    }
+
+    #[test]
+    fn prices_must_align_to_tick() {
+        let tick = Decimal::new(1, 2);
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        assert!(intent.clone().market().validate_tick(tick).is_ok());
+        assert!(intent
+            .clone()
+            .stop_limit(Decimal::new(10001, 2), Decimal::new(1002, 1))
+            .validate_tick(tick)
+            .is_ok());
+        assert!(matches!(
+            intent
+                .clone()
+                .limit(Decimal::new(100005, 3))
+                .validate_tick(tick),
+            Err(Error::InvalidTick(_, _))
+        ));
+        assert!(matches!(
+            intent
+                .stop_limit(Decimal::new(100, 0), Decimal::new(1001, 3))
+                .validate_tick(tick),
+            Err(Error::InvalidTick(_, _))
+        ));
+    }
+
+    #[test]
+    fn can_detect_sequence_gaps() {
+        let mut tracker = SequenceTracker::new();
+        assert_eq!(tracker.observe(1), None);
+        assert_eq!(tracker.observe(2), None);
+        assert_eq!(tracker.observe(3), None);
+        assert_eq!(tracker.observe(6), Some(4..6));
+        assert_eq!(tracker.observe(4), None);
+        assert_eq!(tracker.observe(7), None);
+
+        let envelope =
+            TradeEnvelope::new("strategy", TradeMessage::Cancel { id: Uuid::new_v4() }).sequence(8);
+        let serialized = serde_json::to_string(&envelope).unwrap();
+        assert!(serialized.contains(r#""sequence":8"#));
+        let deserialized: TradeEnvelope = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(tracker.observe(deserialized.sequence.unwrap()), None);
+    }
+
+    #[test]
+    fn can_construct_from_notional() {
+        let intent = TradeIntent::from_notional(
+            "AAPL",
+            Decimal::new(1000, 0),
+            Decimal::new(150, 0),
+            Side::Buy,
+        )
+        .unwrap();
+        assert_eq!(intent.qty(), 7);
+        let intent = TradeIntent::from_notional(
+            "AAPL",
+            Decimal::new(1000, 0),
+            Decimal::new(80, 0),
+            Side::Sell,
+        )
+        .unwrap();
+        assert_eq!(intent.qty(), -13);
+        assert!(matches!(
+            TradeIntent::from_notional("AAPL", Decimal::new(1000, 0), Decimal::ZERO, Side::Buy),
+            Err(Error::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            TradeIntent::from_notional("AAPL", Decimal::new(-1000, 0), Decimal::ONE, Side::Buy),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            TradeIntent::from_notional("AAPL", Decimal::MAX, Decimal::new(1, 28), Side::Buy),
+            Err(Error::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn can_use_execution_presets() {
+        let intent = TradeIntent::aggressive("AAPL", Side::Sell, 10);
+        assert_eq!(intent.side, Side::Sell);
+        assert_eq!(intent.abs_qty(), 10);
+        assert_eq!(intent.order_type, OrderType::Market);
+        assert_eq!(intent.time_in_force, TimeInForce::ImmediateOrCancel);
+
+        let intent = TradeIntent::passive("AAPL", Side::Buy, 10, 150);
+        assert_eq!(intent.side, Side::Buy);
+        assert_eq!(intent.abs_qty(), 10);
+        assert_eq!(
+            intent.order_type,
+            OrderType::Limit {
+                limit_price: Decimal::new(150, 0)
+            }
+        );
+        assert_eq!(intent.time_in_force, TimeInForce::Day);
+    }
+
+    #[test]
+    fn can_read_json_lines_stream() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        let new_message = TradeMessage::New {
+            intent: intent.clone(),
+        };
+        let cancel_message = TradeMessage::Cancel { id: intent.id };
+        let buffer = format!(
+            "{}\n\n{}\n  \n{}",
+            serde_json::to_string(&new_message).unwrap(),
+            serde_json::to_string(&cancel_message).unwrap(),
+            r#"{"action":"unknown"}"#
+        );
+        let messages: Vec<_> = read_messages(buffer.as_bytes()).collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].as_ref().unwrap(), &new_message);
+        assert_eq!(messages[1].as_ref().unwrap(), &cancel_message);
+        assert!(messages[2].is_err());
+    }
+
+    #[test]
+    fn can_simplify_order_type() {
+        let price = Decimal::new(100, 0);
+        let cases = [
+            (OrderType::Market, SimplifiedOrderType::Market),
+            (OrderType::MarketOnOpen, SimplifiedOrderType::Market),
+            (OrderType::MarketOnClose, SimplifiedOrderType::Market),
+            (
+                OrderType::Limit { limit_price: price },
+                SimplifiedOrderType::Limit,
+            ),
+            (
+                OrderType::PeggedToMarket { offset: price },
+                SimplifiedOrderType::Limit,
+            ),
+            (
+                OrderType::Stop { stop_price: price },
+                SimplifiedOrderType::Stop,
+            ),
+            (
+                OrderType::StopLimit {
+                    stop_price: price,
+                    limit_price: price,
+                },
+                SimplifiedOrderType::Stop,
+            ),
+        ];
+        for (order_type, simplified) in cases {
+            assert_eq!(order_type.simplified(), simplified);
+        }
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_nested_order_type() {
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10).stop_limit(100, 101);
+        let nested = NestedTradeIntent(intent.clone());
+        let value = serde_json::to_value(&nested).unwrap();
+        assert_eq!(
+            value["order"],
+            serde_json::json!({
+                "order_type": "stop_limit",
+                "stop_price": "100",
+                "limit_price": "101",
+            })
+        );
+        assert!(value.get("order_type").is_none());
+        assert!(value.get("stop_price").is_none());
+        let deserialized: NestedTradeIntent = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.0, intent);
+
+        let flat = serde_json::to_value(&intent).unwrap();
+        assert!(serde_json::from_value::<NestedTradeIntent>(flat).is_err());
+    }
+
+    #[test]
+    fn good_til_date_requires_good_til_canceled() {
+        let expiry = Utc::now() + chrono::Duration::days(7);
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10)
+            .limit(100)
+            .time_in_force(TimeInForce::GoodTilCanceled)
+            .good_til_date(expiry);
+        assert!(intent.validate().is_ok());
+        let serialized = serde_json::to_string(&intent).unwrap();
+        assert!(serialized.contains("good_til_date"));
+        let deserialized: TradeIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.good_til_date, Some(expiry));
+
+        assert!(matches!(
+            intent.time_in_force(TimeInForce::Day).validate(),
+            Err(Error::InvalidGoodTilDate(TimeInForce::Day))
+        ));
+
+        let intent = TradeIntent::new("AAPL", Side::Buy, 10);
+        assert!(!serde_json::to_string(&intent)
+            .unwrap()
+            .contains("good_til_date"));
+    }
+
+    #[test]
+    fn qty_is_capped_at_isize_max() {
+        let buy = TradeIntent::new("X", Side::Buy, usize::MAX);
+        assert_eq!(buy.qty(), isize::MAX);
+        assert_eq!(buy.abs_qty(), isize::MAX as usize);
+        assert_eq!(buy.simulate_fill(Decimal::ONE).filled_qty, isize::MAX);
+        let sell = buy
+            .clone()
+            .side(Side::Sell)
+            .with_qty(isize::MAX as usize + 1);
+        assert_eq!(sell.qty(), -isize::MAX);
+
+        let serialized = serde_json::to_string(&buy).unwrap();
+        let deserialized: TradeIntent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, buy);
+
+        let sell = TradeIntent::from_signed_qty("X", isize::MIN);
+        assert_eq!(sell.qty(), -isize::MAX);
+    }
 }
@@ -0,0 +1,58 @@
+use serde::{de, Deserialize, Deserializer};
+
+/// Protocol version written by this release of the crate.
+pub const CURRENT_VERSION: u8 = 1;
+
+/// Oldest protocol version this release of the crate can read.
+pub const MIN_SUPPORTED_VERSION: u8 = 1;
+
+pub fn is_supported_version(version: u8) -> bool {
+    (MIN_SUPPORTED_VERSION..=CURRENT_VERSION).contains(&version)
+}
+
+pub(crate) fn current_version() -> u8 {
+    CURRENT_VERSION
+}
+
+pub(crate) fn supported_version<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let version = u8::deserialize(deserializer)?;
+    if !is_supported_version(version) {
+        return Err(de::Error::custom(format!(
+            "unsupported protocol version {}, expected {} to {}",
+            version, MIN_SUPPORTED_VERSION, CURRENT_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PositionIntent;
+
+    #[test]
+    fn can_check_supported_versions() {
+        assert!(is_supported_version(CURRENT_VERSION));
+        assert!(!is_supported_version(0));
+        assert!(!is_supported_version(CURRENT_VERSION + 1));
+    }
+
+    #[test]
+    fn rejects_unsupported_versions_on_deserialize() {
+        let intent = PositionIntent::for_ticker("A", "AAPL").build().unwrap();
+        assert_eq!(intent.version, CURRENT_VERSION);
+        let mut value = serde_json::to_value(&intent).unwrap();
+        assert_eq!(value["version"], CURRENT_VERSION);
+
+        value.as_object_mut().unwrap().remove("version");
+        let deserialized: PositionIntent = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(deserialized.version, CURRENT_VERSION);
+
+        value["version"] = serde_json::json!(CURRENT_VERSION + 1);
+        let error = serde_json::from_value::<PositionIntent>(value).unwrap_err();
+        assert!(error.to_string().contains("unsupported protocol version"));
+    }
+}